@@ -1,20 +1,330 @@
 
+create table sys_dept (
+  id varchar(32) primary key,
+  parent_id varchar(32) references sys_dept (id),
+  name varchar(64) not null,
+  order_num int not null default 0,
+  leader varchar(64),
+  phone varchar(32),
+  enabled bool not null default true,
+  created_at timestamp not null default now(),
+  updated_at timestamp not null default now()
+);
+
+insert into sys_dept (id, parent_id, name, order_num)
+values
+  ('1', null, '总公司', 1),
+  ('2', '1', '研发部', 1),
+  ('3', '1', '市场部', 2);
+
+
 create table sys_user (
-  id serial primary key,
+  id varchar(32) primary key,
   name varchar(255) not null unique,
   gender varchar(255) not null,
-  account varchar(255) not null,
+  account varchar(255) not null unique,
   password varchar(255) not null,
+  password_updated_at timestamp not null default now(),
+  -- 认证方式: local(本地密码) 或外部认证的名称, 外部用户首次登录时自动创建
+  auth_source varchar(32) not null default 'local',
   mobile_phone varchar(255) not null,
+  email varchar(255) unique,
   birthday date not null,
   enabled bool not null,
+  dept_id varchar(32) references sys_dept (id) on delete set null,
   created_at timestamp not null default now(),
-  updated_at timestamp not null
+  updated_at timestamp not null default now()
 );
 
 
-insert into sys_user (name, gender, account, password, mobile_phone, birthday, enabled, created_at, updated_at) 
+-- 密码为 bcrypt 哈希, 明文分别为 admin123 / user123
+insert into sys_user (id, name, gender, account, password, mobile_phone, email, birthday, enabled, dept_id, created_at, updated_at) 
 values 
-  ('admin', 'male', 'admin', 'admin', '12345678', '1999-01-01', true, now(), now()),
-  ('user', 'female', 'user', 'user', '12345678', '1999-01-01', true, now(), now());
+  ('1', 'admin', 'male', 'admin', '$2b$12$jgf4x0u./66vr.uKml.AxOPTXsOdZutx06P5y2.zQE2Oef0W3dsqG', '12345678', 'admin@example.com', '1999-01-01', true, '1', now(), now()),
+  ('2', 'user', 'female', 'user', '$2b$12$xAyai/IV.iMD7q759SK7.eMOU4A5JfVkF1OZo3xW3FoAApa.cEvYi', '12345678', 'user@example.com', '1999-01-01', true, '2', now(), now());
+
+
+
+-- 最近使用过的密码哈希, 用于禁止重复使用
+create table sys_password_history (
+  id varchar(32) primary key,
+  user_id varchar(32) not null references sys_user (id) on delete cascade,
+  password varchar(255) not null,
+  created_at timestamp not null default now()
+);
+
+create index idx_sys_password_history_user on sys_password_history (user_id);
+
+
+-- 找回密码的一次性 token, 只保存哈希
+create table sys_password_reset (
+  id varchar(32) primary key,
+  user_id varchar(32) not null references sys_user (id) on delete cascade,
+  token_hash varchar(64) not null unique,
+  expires_at timestamp not null,
+  used_at timestamp,
+  created_at timestamp not null default now()
+);
+
+
+-- TOTP 两步验证
+create table sys_user_mfa (
+  user_id varchar(32) primary key references sys_user (id) on delete cascade,
+  secret varchar(64) not null,
+  enabled bool not null default false,
+  last_used_step bigint,
+  created_at timestamp not null default now(),
+  updated_at timestamp not null default now()
+);
+
+-- 两步验证的恢复码, 只保存哈希
+create table sys_mfa_recovery_code (
+  id varchar(32) primary key,
+  user_id varchar(32) not null references sys_user (id) on delete cascade,
+  code_hash varchar(64) not null unique,
+  used_at timestamp,
+  created_at timestamp not null default now()
+);
+
+create index idx_sys_mfa_recovery_code_user on sys_mfa_recovery_code (user_id);
+
+
+-- 机器客户端使用的 API key, 只保存哈希
+create table sys_api_key (
+  id varchar(32) primary key,
+  user_id varchar(32) not null references sys_user (id) on delete cascade,
+  name varchar(64) not null,
+  -- key 的前几位, 用于界面展示和识别
+  prefix varchar(16) not null,
+  key_hash varchar(64) not null unique,
+  -- 允许使用的权限标识, 与用户自身权限取交集
+  scopes varchar(128)[] not null default '{}',
+  expires_at timestamp,
+  last_used_at timestamp,
+  revoked_at timestamp,
+  created_at timestamp not null default now()
+);
+
+create index idx_sys_api_key_user on sys_api_key (user_id);
+
+
+create table sys_refresh_token (
+  id varchar(32) primary key,
+  user_id varchar(32) not null,
+  family_id varchar(32) not null,
+  token_hash varchar(64) not null unique,
+  expires_at timestamp not null,
+  used_at timestamp,
+  revoked_at timestamp,
+  created_at timestamp not null default now()
+);
+
+create index idx_sys_refresh_token_family on sys_refresh_token (family_id);
+create index idx_sys_refresh_token_user on sys_refresh_token (user_id);
+
+
+-- 在线会话, 一次登录对应一个 refresh token family, id 与 family_id 相同
+create table sys_user_session (
+  id varchar(32) primary key,
+  user_id varchar(32) not null references sys_user (id) on delete cascade,
+  -- 最近一次签发的 access token
+  jti varchar(32) not null,
+  ip varchar(64) not null,
+  user_agent varchar(512),
+  login_at timestamp not null,
+  last_seen_at timestamp not null,
+  -- refresh token 过期后会话结束
+  expires_at timestamp not null,
+  revoked_at timestamp
+);
+
+create index idx_sys_user_session_user on sys_user_session (user_id);
+
+
+-- 登录日志, 每次登录尝试都会记录
+create table sys_login_log (
+  id varchar(32) primary key,
+  account varchar(64) not null,
+  success bool not null,
+  -- 成功或失败原因
+  message varchar(255) not null,
+  ip varchar(64) not null,
+  user_agent varchar(512),
+  browser varchar(64),
+  os varchar(64),
+  created_at timestamp not null default now()
+);
+
+create index idx_sys_login_log_account on sys_login_log (account);
+create index idx_sys_login_log_created_at on sys_login_log (created_at);
+
+
+-- 操作日志, 记录修改数据的接口调用
+create table sys_oper_log (
+  id varchar(32) primary key,
+  -- 模块和操作, 如 用户管理 / 删除用户
+  module varchar(64) not null,
+  action varchar(64) not null,
+  user_id varchar(32),
+  user_name varchar(255),
+  method varchar(16) not null,
+  path varchar(255) not null,
+  -- 请求参数, 密码等敏感字段已脱敏
+  params text,
+  status int not null,
+  success bool not null,
+  error_msg text,
+  -- 耗时(毫秒)
+  latency bigint not null,
+  ip varchar(64) not null,
+  created_at timestamp not null default now()
+);
+
+create index idx_sys_oper_log_user on sys_oper_log (user_id);
+create index idx_sys_oper_log_created_at on sys_oper_log (created_at);
+
+
+create table sys_token_revocation (
+  id varchar(32) primary key,
+  jti varchar(32),
+  user_id varchar(32),
+  issued_before bigint,
+  expires_at bigint not null,
+  created_at timestamp not null default now()
+);
+
+create index idx_sys_token_revocation_jti on sys_token_revocation (jti);
+create index idx_sys_token_revocation_user on sys_token_revocation (user_id);
+
+
+create table sys_role (
+  id varchar(32) primary key,
+  code varchar(64) not null unique,
+  name varchar(255) not null,
+  description varchar(255),
+  enabled bool not null default true,
+  data_scope varchar(32) not null default 'all',
+  created_at timestamp not null default now(),
+  updated_at timestamp not null default now()
+);
+
+create table sys_permission (
+  id varchar(32) primary key,
+  code varchar(128) not null unique,
+  name varchar(255) not null,
+  description varchar(255),
+  created_at timestamp not null default now(),
+  updated_at timestamp not null default now()
+);
+
+create table sys_user_role (
+  user_id varchar(32) not null references sys_user (id) on delete cascade,
+  role_id varchar(32) not null references sys_role (id) on delete cascade,
+  primary key (user_id, role_id)
+);
+
+create table sys_role_permission (
+  role_id varchar(32) not null references sys_role (id) on delete cascade,
+  permission_id varchar(32) not null references sys_permission (id) on delete cascade,
+  primary key (role_id, permission_id)
+);
+
+create table sys_role_dept (
+  role_id varchar(32) not null references sys_role (id) on delete cascade,
+  dept_id varchar(32) not null references sys_dept (id) on delete cascade,
+  primary key (role_id, dept_id)
+);
+
+create table sys_menu (
+  id varchar(32) primary key,
+  parent_id varchar(32) references sys_menu (id),
+  menu_type varchar(16) not null,
+  name varchar(64) not null,
+  path varchar(255),
+  component varchar(255),
+  icon varchar(64),
+  order_num int not null default 0,
+  permission varchar(128),
+  visible bool not null default true,
+  created_at timestamp not null default now(),
+  updated_at timestamp not null default now()
+);
+
+create table sys_role_menu (
+  role_id varchar(32) not null references sys_role (id) on delete cascade,
+  menu_id varchar(32) not null references sys_menu (id) on delete cascade,
+  primary key (role_id, menu_id)
+);
+
+
+-- 内置超级管理员角色, 拥有所有权限
+insert into sys_role (id, code, name, description, enabled)
+values
+  ('1', 'super_admin', '超级管理员', '内置角色, 跳过权限校验', true);
+
+insert into sys_permission (id, code, name)
+values
+  ('1', 'system:user:list', '查询用户'),
+  ('2', 'system:user:create', '新增用户'),
+  ('3', 'system:user:update', '修改用户'),
+  ('4', 'system:user:delete', '删除用户'),
+  ('5', 'system:user:revoke', '强制用户下线'),
+  ('6', 'system:user:role', '分配用户角色'),
+  ('7', 'system:role:list', '查询角色'),
+  ('8', 'system:role:create', '新增角色'),
+  ('9', 'system:role:update', '修改角色'),
+  ('10', 'system:role:delete', '删除角色'),
+  ('11', 'system:role:permission', '分配角色权限'),
+  ('12', 'system:permission:list', '查询权限'),
+  ('13', 'system:permission:create', '新增权限'),
+  ('14', 'system:permission:update', '修改权限'),
+  ('15', 'system:permission:delete', '删除权限'),
+  ('16', 'system:menu:list', '查询菜单'),
+  ('17', 'system:menu:create', '新增菜单'),
+  ('18', 'system:menu:update', '修改菜单'),
+  ('19', 'system:menu:delete', '删除菜单'),
+  ('20', 'system:role:menu', '分配角色菜单'),
+  ('21', 'system:dept:list', '查询部门'),
+  ('22', 'system:dept:create', '新增部门'),
+  ('23', 'system:dept:update', '修改部门'),
+  ('24', 'system:dept:delete', '删除部门'),
+  ('25', 'system:role:scope', '分配角色数据权限'),
+  ('26', 'system:user:unlock', '解除用户锁定'),
+  ('27', 'system:user:mfa', '重置用户两步验证'),
+  ('28', 'system:session:list', '查询在线会话'),
+  ('29', 'system:session:kick', '强制会话下线'),
+  ('30', 'system:loginlog:list', '查询登录日志'),
+  ('31', 'system:loginlog:delete', '清理登录日志'),
+  ('32', 'system:operlog:list', '查询操作日志'),
+  ('33', 'system:operlog:delete', '清理操作日志');
+
+insert into sys_menu (id, parent_id, menu_type, name, path, component, icon, order_num, permission)
+values
+  ('1', null, 'directory', '系统管理', '/system', null, 'setting', 1, null),
+  ('2', '1', 'menu', '用户管理', 'user', 'system/user/index', 'user', 1, 'system:user:list'),
+  ('3', '1', 'menu', '角色管理', 'role', 'system/role/index', 'peoples', 2, 'system:role:list'),
+  ('4', '1', 'menu', '权限管理', 'permission', 'system/permission/index', 'lock', 3, 'system:permission:list'),
+  ('5', '1', 'menu', '菜单管理', 'menu', 'system/menu/index', 'tree-table', 4, 'system:menu:list'),
+  ('6', '2', 'button', '新增用户', null, null, null, 1, 'system:user:create'),
+  ('7', '2', 'button', '修改用户', null, null, null, 2, 'system:user:update'),
+  ('8', '2', 'button', '删除用户', null, null, null, 3, 'system:user:delete'),
+  ('9', '3', 'button', '新增角色', null, null, null, 1, 'system:role:create'),
+  ('10', '3', 'button', '修改角色', null, null, null, 2, 'system:role:update'),
+  ('11', '3', 'button', '删除角色', null, null, null, 3, 'system:role:delete'),
+  ('12', '5', 'button', '新增菜单', null, null, null, 1, 'system:menu:create'),
+  ('13', '5', 'button', '修改菜单', null, null, null, 2, 'system:menu:update'),
+  ('14', '5', 'button', '删除菜单', null, null, null, 3, 'system:menu:delete'),
+  ('15', '1', 'menu', '部门管理', 'dept', 'system/dept/index', 'tree', 5, 'system:dept:list'),
+  ('16', '15', 'button', '新增部门', null, null, null, 1, 'system:dept:create'),
+  ('17', '15', 'button', '修改部门', null, null, null, 2, 'system:dept:update'),
+  ('18', '15', 'button', '删除部门', null, null, null, 3, 'system:dept:delete'),
+  ('19', '1', 'menu', '在线用户', 'session', 'system/session/index', 'online', 6, 'system:session:list'),
+  ('20', '19', 'button', '强制下线', null, null, null, 1, 'system:session:kick'),
+  ('21', '1', 'menu', '登录日志', 'login-log', 'system/login-log/index', 'logininfor', 7, 'system:loginlog:list'),
+  ('22', '21', 'button', '清理日志', null, null, null, 1, 'system:loginlog:delete'),
+  ('23', '1', 'menu', '操作日志', 'oper-log', 'system/oper-log/index', 'form', 8, 'system:operlog:list'),
+  ('24', '23', 'button', '清理日志', null, null, null, 1, 'system:operlog:delete');
 
+insert into sys_user_role (user_id, role_id)
+values
+  ('1', '1');
@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+const DEFAULT_LOGIN_LOG_RETENTION_DAYS: u32 = 90;
+const DEFAULT_OPER_LOG_RETENTION_DAYS: u32 = 180;
+
+// 审计日志
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditConfig {
+    // 登录日志保留天数, 为 0 时不自动清理
+    login_log_retention_days: Option<u32>,
+    // 操作日志保留天数, 为 0 时不自动清理
+    oper_log_retention_days: Option<u32>,
+}
+
+impl AuditConfig {
+    pub fn login_log_retention_days(&self) -> u32 {
+        self.login_log_retention_days.unwrap_or(DEFAULT_LOGIN_LOG_RETENTION_DAYS)
+    }
+
+    pub fn oper_log_retention_days(&self) -> u32 {
+        self.oper_log_retention_days.unwrap_or(DEFAULT_OPER_LOG_RETENTION_DAYS)
+    }
+}
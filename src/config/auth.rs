@@ -0,0 +1,501 @@
+use serde::{Deserialize, Serialize};
+
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuthConfig {
+    // 从 cookie 中读取 token 时使用的 cookie 名称, 不配置则只读取 Authorization 头
+    cookie: Option<String>,
+    // 已吊销 token 的存储方式, 多实例部署时使用 database
+    #[serde(default)]
+    revocation: RevocationStoreKind,
+    #[serde(default)]
+    jwt: JwtConfig,
+    #[serde(default)]
+    lockout: LockoutConfig,
+    #[serde(default)]
+    password: PasswordPolicyConfig,
+    #[serde(default)]
+    reset_password: ResetPasswordConfig,
+    #[serde(default)]
+    mfa: MfaConfig,
+    #[serde(default)]
+    captcha: CaptchaConfig,
+    // 按顺序尝试的认证方式, 不配置时只使用本地密码
+    #[serde(default)]
+    providers: Vec<AuthProviderConfig>,
+}
+
+impl AuthConfig {
+    pub fn cookie(&self) -> Option<&str> {
+        self.cookie.as_deref()
+    }
+
+    pub fn jwt(&self) -> &JwtConfig {
+        &self.jwt
+    }
+
+    pub fn revocation(&self) -> RevocationStoreKind {
+        self.revocation
+    }
+
+    pub fn lockout(&self) -> &LockoutConfig {
+        &self.lockout
+    }
+
+    pub fn password(&self) -> &PasswordPolicyConfig {
+        &self.password
+    }
+
+    pub fn reset_password(&self) -> &ResetPasswordConfig {
+        &self.reset_password
+    }
+
+    pub fn mfa(&self) -> &MfaConfig {
+        &self.mfa
+    }
+
+    pub fn captcha(&self) -> &CaptchaConfig {
+        &self.captcha
+    }
+
+    pub fn providers(&self) -> &[AuthProviderConfig] {
+        &self.providers
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationStoreKind {
+    // 单实例, 保存在内存中
+    #[default]
+    Memory,
+    // 多实例共享, 保存在 sys_token_revocation 表中
+    Database,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct JwtConfig {
+    // 签名秘钥, 与 secret_file 二选一
+    secret: Option<String>,
+    // 从文件中读取签名秘钥
+    secret_file: Option<String>,
+    issuer: Option<String>,
+    audience: Option<String>,
+    // access token 有效时长(秒)
+    access_ttl: Option<u64>,
+    // refresh token 有效时长(秒), 每次轮换重新计算
+    refresh_ttl: Option<u64>,
+    // 校验 exp 时允许的时钟偏差(秒)
+    leeway: Option<u64>,
+    // 当前用于签名的 key, 不配置时使用 keys 中第一个带私钥的 key
+    kid: Option<String>,
+    // 非对称 key, 配置后不再使用 secret; 轮换时旧 key 保留公钥继续参与校验
+    #[serde(default)]
+    keys: Vec<JwtKeyConfig>,
+}
+
+impl JwtConfig {
+    pub fn secret(&self) -> Option<&str> {
+        self.secret.as_deref()
+    }
+
+    pub fn secret_file(&self) -> Option<&str> {
+        self.secret_file.as_deref()
+    }
+
+    pub fn issuer(&self) -> &str {
+        self.issuer.as_deref().unwrap_or("issuer")
+    }
+
+    pub fn audience(&self) -> &str {
+        self.audience.as_deref().unwrap_or("audience")
+    }
+
+    pub fn access_ttl(&self) -> u64 {
+        self.access_ttl.unwrap_or(60 * 60)
+    }
+
+    pub fn refresh_ttl(&self) -> u64 {
+        self.refresh_ttl.unwrap_or(30 * 24 * 60 * 60)
+    }
+
+    pub fn leeway(&self) -> u64 {
+        self.leeway.unwrap_or(60)
+    }
+
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    pub fn keys(&self) -> &[JwtKeyConfig] {
+        &self.keys
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JwtKeyConfig {
+    kid: String,
+    // RS256 / ES256 / EdDSA 等
+    algorithm: String,
+    // 私钥 PEM 文件, 只用于校验的 key 可以不配置
+    private_key: Option<String>,
+    // 公钥 PEM 文件
+    public_key: String,
+}
+
+impl JwtKeyConfig {
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    pub fn algorithm(&self) -> &str {
+        &self.algorithm
+    }
+
+    pub fn private_key(&self) -> Option<&str> {
+        self.private_key.as_deref()
+    }
+
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LockoutConfig {
+    // 同一账号在统计窗口内允许的连续失败次数
+    max_attempts: Option<u32>,
+    // 同一 IP 在统计窗口内允许的失败次数
+    ip_max_attempts: Option<u32>,
+    // 失败次数的统计窗口(秒)
+    window: Option<u64>,
+    // 第一次锁定的时长(秒), 之后每次锁定时长翻倍
+    lock_duration: Option<u64>,
+    // 锁定时长上限(秒)
+    max_lock_duration: Option<u64>,
+}
+
+impl LockoutConfig {
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.unwrap_or(5)
+    }
+
+    pub fn ip_max_attempts(&self) -> u32 {
+        self.ip_max_attempts.unwrap_or(20)
+    }
+
+    pub fn window(&self) -> u64 {
+        self.window.unwrap_or(15 * 60)
+    }
+
+    pub fn lock_duration(&self) -> u64 {
+        self.lock_duration.unwrap_or(60)
+    }
+
+    pub fn max_lock_duration(&self) -> u64 {
+        self.max_lock_duration.unwrap_or(60 * 60)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PasswordPolicyConfig {
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    // 必须包含的字符种类
+    require_lowercase: Option<bool>,
+    require_uppercase: Option<bool>,
+    require_digit: Option<bool>,
+    require_symbol: Option<bool>,
+    // 禁止使用的常见密码, 不区分大小写
+    #[serde(default)]
+    banned: Vec<String>,
+    // 禁止使用的密码文件, 每行一个
+    banned_file: Option<String>,
+    // 不能与最近几次使用过的密码相同, 0 表示不限制
+    history: Option<usize>,
+    // 密码有效天数, 过期后登录时必须先修改密码, 不配置则永不过期
+    max_age_days: Option<u64>,
+}
+
+impl PasswordPolicyConfig {
+    pub fn min_length(&self) -> usize {
+        self.min_length.unwrap_or(8)
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.max_length.unwrap_or(64)
+    }
+
+    pub fn require_lowercase(&self) -> bool {
+        self.require_lowercase.unwrap_or(true)
+    }
+
+    pub fn require_uppercase(&self) -> bool {
+        self.require_uppercase.unwrap_or(false)
+    }
+
+    pub fn require_digit(&self) -> bool {
+        self.require_digit.unwrap_or(true)
+    }
+
+    pub fn require_symbol(&self) -> bool {
+        self.require_symbol.unwrap_or(false)
+    }
+
+    pub fn banned(&self) -> &[String] {
+        &self.banned
+    }
+
+    pub fn banned_file(&self) -> Option<&str> {
+        self.banned_file.as_deref()
+    }
+
+    pub fn history(&self) -> usize {
+        self.history.unwrap_or(5)
+    }
+
+    pub fn max_age_days(&self) -> Option<u64> {
+        self.max_age_days
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ResetPasswordConfig {
+    // 重置链接有效时长(秒)
+    ttl: Option<u64>,
+    // 邮件中的重置链接, {token} 会被替换为重置 token
+    url: Option<String>,
+    // 同一邮箱在统计窗口内允许的请求次数, 窗口和限制时长与登录锁定相同
+    max_requests: Option<u32>,
+    // 同一 IP 在统计窗口内允许的请求次数
+    ip_max_requests: Option<u32>,
+}
+
+impl ResetPasswordConfig {
+    pub fn ttl(&self) -> u64 {
+        self.ttl.unwrap_or(30 * 60)
+    }
+
+    pub fn url(&self) -> &str {
+        self.url.as_deref().unwrap_or("http://localhost:5173/reset-password?token={token}")
+    }
+
+    pub fn max_requests(&self) -> u32 {
+        self.max_requests.unwrap_or(3)
+    }
+
+    pub fn ip_max_requests(&self) -> u32 {
+        self.ip_max_requests.unwrap_or(10)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MfaConfig {
+    // 验证器 App 中显示的发行方
+    issuer: Option<String>,
+    // 登录第一步返回的临时 token 有效时长(秒)
+    token_ttl: Option<u64>,
+    // 允许前后偏差的时间步数(每步 30 秒)
+    skew: Option<u8>,
+    // 生成的恢复码数量
+    recovery_codes: Option<usize>,
+}
+
+impl MfaConfig {
+    pub fn issuer(&self) -> &str {
+        self.issuer.as_deref().unwrap_or("rust-axum")
+    }
+
+    pub fn token_ttl(&self) -> u64 {
+        self.token_ttl.unwrap_or(5 * 60)
+    }
+
+    pub fn skew(&self) -> u8 {
+        self.skew.unwrap_or(1)
+    }
+
+    pub fn recovery_codes(&self) -> usize {
+        self.recovery_codes.unwrap_or(10)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptchaKind {
+    // 随机字符
+    #[default]
+    Image,
+    // 算术题, 答案为计算结果
+    Arithmetic,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CaptchaConfig {
+    // 登录、修改密码和忘记密码时是否必须提交图形验证码
+    login: Option<bool>,
+    #[serde(default)]
+    kind: CaptchaKind,
+    // 有效时长(秒)
+    ttl: Option<u64>,
+    // 随机字符的个数
+    length: Option<usize>,
+    // 同一 IP 在 lockout.window 内允许获取的次数, 超过后按 lockout.lock_duration 限制
+    ip_max_requests: Option<u32>,
+}
+
+impl CaptchaConfig {
+    pub fn login(&self) -> bool {
+        self.login.unwrap_or(true)
+    }
+
+    pub fn kind(&self) -> CaptchaKind {
+        self.kind
+    }
+
+    pub fn ttl(&self) -> u64 {
+        self.ttl.unwrap_or(2 * 60)
+    }
+
+    pub fn length(&self) -> usize {
+        self.length.unwrap_or(4).clamp(1, 8)
+    }
+
+    pub fn ip_max_requests(&self) -> u32 {
+        self.ip_max_requests.unwrap_or(30)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthProviderConfig {
+    // sys_user 中的 bcrypt 密码
+    Local,
+    // LDAP / Active Directory 绑定
+    Ldap(Box<LdapProviderConfig>),
+    // 静态用户文件, 用于系统初始化
+    File(FileProviderConfig),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LdapProviderConfig {
+    // 记录在 sys_user.auth_source 中, 配置多个 LDAP 时需要区分
+    name: Option<String>,
+    // ldap://host:389 或 ldaps://host:636
+    url: String,
+    #[serde(default)]
+    starttls: bool,
+    // 连接超时(秒)
+    timeout: Option<u64>,
+    // 搜索用户时使用的服务账号, 不配置则匿名搜索
+    bind_dn: Option<String>,
+    bind_password: Option<String>,
+    // 搜索用户的起点
+    base_dn: Option<String>,
+    // 搜索用户的过滤条件, {account} 替换为转义后的账号
+    user_filter: Option<String>,
+    // 配置后直接使用该 DN 绑定, 不再先搜索; AD 可以使用 {account}@example.com
+    user_dn: Option<String>,
+    #[serde(default)]
+    attributes: LdapAttributeMapping,
+    // 首次登录自动创建用户时分配的角色编码
+    #[serde(default)]
+    default_roles: Vec<String>,
+}
+
+impl LdapProviderConfig {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("ldap")
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn starttls(&self) -> bool {
+        self.starttls
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout.unwrap_or(5)
+    }
+
+    pub fn bind_dn(&self) -> Option<&str> {
+        self.bind_dn.as_deref()
+    }
+
+    pub fn bind_password(&self) -> &str {
+        self.bind_password.as_deref().unwrap_or_default()
+    }
+
+    pub fn base_dn(&self) -> Option<&str> {
+        self.base_dn.as_deref()
+    }
+
+    pub fn user_filter(&self) -> &str {
+        self.user_filter.as_deref().unwrap_or("(uid={account})")
+    }
+
+    pub fn user_dn(&self) -> Option<&str> {
+        self.user_dn.as_deref()
+    }
+
+    pub fn attributes(&self) -> &LdapAttributeMapping {
+        &self.attributes
+    }
+
+    pub fn default_roles(&self) -> &[String] {
+        &self.default_roles
+    }
+}
+
+// 目录属性到 sys_user 字段的映射
+#[derive(Debug, Default, Deserialize)]
+pub struct LdapAttributeMapping {
+    // 目录中的账号, 作为 sys_user.account, AD 使用 sAMAccountName
+    account: Option<String>,
+    name: Option<String>,
+    email: Option<String>,
+    mobile_phone: Option<String>,
+}
+
+impl LdapAttributeMapping {
+    pub fn account(&self) -> &str {
+        self.account.as_deref().unwrap_or("uid")
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("cn")
+    }
+
+    pub fn email(&self) -> &str {
+        self.email.as_deref().unwrap_or("mail")
+    }
+
+    pub fn mobile_phone(&self) -> &str {
+        self.mobile_phone.as_deref().unwrap_or("mobile")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileProviderConfig {
+    name: Option<String>,
+    // yaml 文件, users 列表中每项包含 account、password(bcrypt 哈希)、name 等
+    path: String,
+    // 文件中的用户没有配置角色时分配的角色编码
+    #[serde(default)]
+    default_roles: Vec<String>,
+}
+
+impl FileProviderConfig {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("file")
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn default_roles(&self) -> &[String] {
+        &self.default_roles
+    }
+}
@@ -0,0 +1,88 @@
+use serde::Deserialize;
+
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MailConfig {
+    #[serde(default)]
+    transport: MailTransportKind,
+    // 发件人, 如 "Rust Axum <noreply@example.com>"
+    from: Option<String>,
+    // transport 为 outbox 时邮件写入的目录
+    outbox_dir: Option<String>,
+    #[serde(default)]
+    smtp: SmtpConfig,
+}
+
+impl MailConfig {
+    pub fn transport(&self) -> MailTransportKind {
+        self.transport
+    }
+
+    pub fn from(&self) -> &str {
+        self.from.as_deref().unwrap_or("noreply@localhost")
+    }
+
+    pub fn outbox_dir(&self) -> &str {
+        self.outbox_dir.as_deref().unwrap_or("outbox")
+    }
+
+    pub fn smtp(&self) -> &SmtpConfig {
+        &self.smtp
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailTransportKind {
+    // 只打印到日志, 用于开发环境
+    #[default]
+    Log,
+    // 写入 outbox 目录, 每封邮件一个 .eml 文件
+    Outbox,
+    Smtp,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SmtpConfig {
+    host: Option<String>,
+    port: Option<u16>,
+    username: Option<String>,
+    password: Option<String>,
+    #[serde(default)]
+    tls: SmtpTls,
+}
+
+impl SmtpConfig {
+    pub fn host(&self) -> &str {
+        self.host.as_deref().unwrap_or("127.0.0.1")
+    }
+
+    // 不配置时使用 tls 方式对应的默认端口
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    pub fn username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
+
+    pub fn password(&self) -> Option<&str> {
+        self.password.as_deref()
+    }
+
+    pub fn tls(&self) -> SmtpTls {
+        self.tls
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    // 明文连接后升级为 tls, 默认端口 587
+    #[default]
+    Starttls,
+    // 直接使用 tls 连接, 默认端口 465
+    Tls,
+    // 不加密, 只用于本地测试
+    None,
+}
@@ -1,11 +1,17 @@
 pub mod server;
 pub mod database;
+pub mod auth;
+pub mod mail;
+pub mod audit;
 
 use std::sync::LazyLock;
 
 use anyhow::Context;
+use audit::AuditConfig;
+use auth::AuthConfig;
 use config::{Config, Environment, File, FileFormat};
 use database::DatabaseConfig;
+use mail::MailConfig;
 use serde::Deserialize;
 use server::ServerConfig;
 
@@ -16,8 +22,16 @@ static CONFIG: LazyLock<AppConfig> = LazyLock::new(||AppConfig::load().expect("F
 
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
+    // 运行环境, 如 dev / test / prod
+    profile: Option<String>,
     server: ServerConfig,
-    database: DatabaseConfig
+    database: DatabaseConfig,
+    #[serde(default)]
+    auth: AuthConfig,
+    #[serde(default)]
+    mail: MailConfig,
+    #[serde(default)]
+    audit: AuditConfig,
 }
 
 impl AppConfig {
@@ -29,11 +43,19 @@ impl AppConfig {
                     .format(FileFormat::Yaml)
                     .required(true)
             )
+            // 本地覆盖的配置, 如 profile: dev
+            .add_source(
+                File::with_name("application-local")
+                    .format(FileFormat::Yaml)
+                    .required(false)
+            )
             .add_source(
                 Environment::with_prefix("APP")
                     .try_parsing(true)
                     .separator("_")
                     .list_separator(",")
+                    // 只有列出的键按列表解析, 其余仍为字符串, 如 APP_AUTH_JWT_SECRET
+                    .with_list_parse_key("auth.password.banned")
             )
             .build()
             .with_context(|| anyhow::anyhow!("Fail to load config"))? 
@@ -41,6 +63,14 @@ impl AppConfig {
             .with_context(|| anyhow::anyhow!("Fail to deserialize config"))
     }
 
+    pub fn profile(&self) -> &str {
+        self.profile.as_deref().unwrap_or("prod")
+    }
+
+    pub fn is_dev(&self) -> bool {
+        self.profile() == "dev"
+    }
+
     pub fn server(&self) -> &ServerConfig {
         &self.server
     }
@@ -48,6 +78,18 @@ impl AppConfig {
     pub fn database(&self) -> &DatabaseConfig {
         &self.database
     }
+
+    pub fn auth(&self) -> &AuthConfig {
+        &self.auth
+    }
+
+    pub fn mail(&self) -> &MailConfig {
+        &self.mail
+    }
+
+    pub fn audit(&self) -> &AuditConfig {
+        &self.audit
+    }
 }
 
 // 暴露公共方法
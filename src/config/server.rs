@@ -1,14 +1,42 @@
-use serde::Deserialize;
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+use serde::{Deserialize, Deserializer};
 
 
 #[derive(Debug, Deserialize)]
 pub struct ServerConfig {
-    port: Option<u16>
+    port: Option<u16>,
+    // 可信的反向代理, 只有来自这些地址的 X-Forwarded-For 才会被采用
+    #[serde(default, deserialize_with = "deserialize_networks")]
+    trusted_proxies: Vec<IpNet>,
 }
 
 impl ServerConfig {
     pub fn port(&self) -> u16 {
         self.port.unwrap_or(3000)
     }
+
+    pub fn trusted_proxies(&self) -> &[IpNet] {
+        &self.trusted_proxies
+    }
+
+    pub fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|network| network.contains(&ip))
+    }
 }
 
+// 支持单个地址(10.0.0.1)和网段(10.0.0.0/8)
+fn deserialize_networks<'de, D>(deserializer: D) -> Result<Vec<IpNet>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|value| {
+            value.parse::<IpNet>()
+                .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| serde::de::Error::custom(format!("invalid trusted proxy: {}", value)))
+        })
+        .collect()
+}
@@ -1,4 +1,22 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.11
 
 pub mod prelude;
+pub mod sys_api_key;
+pub mod sys_dept;
+pub mod sys_login_log;
+pub mod sys_menu;
+pub mod sys_mfa_recovery_code;
+pub mod sys_oper_log;
+pub mod sys_password_history;
+pub mod sys_password_reset;
+pub mod sys_permission;
+pub mod sys_refresh_token;
+pub mod sys_role;
+pub mod sys_role_dept;
+pub mod sys_role_menu;
+pub mod sys_role_permission;
+pub mod sys_token_revocation;
 pub mod sys_user;
+pub mod sys_user_mfa;
+pub mod sys_user_role;
+pub mod sys_user_session;
@@ -1,3 +1,21 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.11
 
+pub use super::sys_api_key::Entity as SysApiKey;
+pub use super::sys_dept::Entity as SysDept;
+pub use super::sys_login_log::Entity as SysLoginLog;
+pub use super::sys_menu::Entity as SysMenu;
+pub use super::sys_mfa_recovery_code::Entity as SysMfaRecoveryCode;
+pub use super::sys_oper_log::Entity as SysOperLog;
+pub use super::sys_password_history::Entity as SysPasswordHistory;
+pub use super::sys_password_reset::Entity as SysPasswordReset;
+pub use super::sys_permission::Entity as SysPermission;
+pub use super::sys_refresh_token::Entity as SysRefreshToken;
+pub use super::sys_role::Entity as SysRole;
+pub use super::sys_role_dept::Entity as SysRoleDept;
+pub use super::sys_role_menu::Entity as SysRoleMenu;
+pub use super::sys_role_permission::Entity as SysRolePermission;
+pub use super::sys_token_revocation::Entity as SysTokenRevocation;
 pub use super::sys_user::Entity as SysUser;
+pub use super::sys_user_mfa::Entity as SysUserMfa;
+pub use super::sys_user_role::Entity as SysUserRole;
+pub use super::sys_user_session::Entity as SysUserSession;
@@ -16,18 +16,97 @@ pub struct Model {
     #[sea_orm(unique)]
     pub name: String,
     pub gender: Gender,
+    #[sea_orm(unique)]
     pub account: String,
     #[serde(skip_serializing)]
     pub password: String,
+    // 最近一次修改密码的时间, 用于判断密码是否过期
+    pub password_updated_at: DateTime,
+    // local 或外部认证方式的名称, 外部用户的密码不在本系统中
+    pub auth_source: String,
     pub mobile_phone: String,
+    // 用于接收找回密码等邮件
+    pub email: Option<String>,
     pub birthday: Date,
     pub enabled: bool,
+    pub dept_id: Option<String>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::sys_dept::Entity",
+        from = "Column::DeptId",
+        to = "super::sys_dept::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    SysDept,
+    #[sea_orm(has_many = "super::sys_api_key::Entity")]
+    SysApiKey,
+    #[sea_orm(has_many = "super::sys_mfa_recovery_code::Entity")]
+    SysMfaRecoveryCode,
+    #[sea_orm(has_many = "super::sys_password_history::Entity")]
+    SysPasswordHistory,
+    #[sea_orm(has_many = "super::sys_password_reset::Entity")]
+    SysPasswordReset,
+    #[sea_orm(has_one = "super::sys_user_mfa::Entity")]
+    SysUserMfa,
+    #[sea_orm(has_many = "super::sys_user_role::Entity")]
+    SysUserRole,
+    #[sea_orm(has_many = "super::sys_user_session::Entity")]
+    SysUserSession,
+}
+
+impl Related<super::sys_api_key::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SysApiKey.def()
+    }
+}
+
+impl Related<super::sys_dept::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SysDept.def()
+    }
+}
+
+impl Related<super::sys_mfa_recovery_code::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SysMfaRecoveryCode.def()
+    }
+}
+
+impl Related<super::sys_password_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SysPasswordHistory.def()
+    }
+}
+
+impl Related<super::sys_password_reset::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SysPasswordReset.def()
+    }
+}
+
+impl Related<super::sys_user_mfa::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SysUserMfa.def()
+    }
+}
+
+impl Related<super::sys_user_role::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SysUserRole.def()
+    }
+}
+
+impl Related<super::sys_user_session::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SysUserSession.def()
+    }
+}
 
 #[async_trait]
 impl ActiveModelBehavior for ActiveModel {
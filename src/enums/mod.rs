@@ -1,6 +1,8 @@
 
 
 
+use std::str::FromStr;
+
 use serde::{Serialize};
 use serde::Deserialize;
 use sea_orm::{prelude::*, ActiveValue, IntoActiveValue};
@@ -19,6 +21,18 @@ pub enum Gender {
     // #[sea_orm(string_value = "02")] // 数据库存储的时候
     // #[serde(rename = "02")]  // 序列化的时候 
     Female,
+
+    // 外部认证首次登录自动创建的用户, 目录中没有性别信息
+    Unknown,
+}
+
+// 用于 query 参数的解析, 与数据库中保存的值一致
+impl FromStr for Gender {
+    type Err = DbErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Gender::try_from_value(&s.to_string())
+    }
 }
 
 impl IntoActiveValue<Gender> for Gender {
@@ -26,3 +40,45 @@ impl IntoActiveValue<Gender> for Gender {
         ActiveValue::Set(self)
     }
 }
+
+// 菜单类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "menu_type", rename_all = "snake_case")]
+pub enum MenuType {
+    // 目录
+    Directory,
+    // 菜单(页面)
+    Menu,
+    // 按钮(页面内的操作)
+    Button,
+}
+
+impl IntoActiveValue<MenuType> for MenuType {
+    fn into_active_value(self) -> ActiveValue<MenuType> {
+        ActiveValue::Set(self)
+    }
+}
+
+// 角色的数据权限范围, 用户拥有多个角色时取并集
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)", enum_name = "data_scope", rename_all = "snake_case")]
+pub enum DataScope {
+    // 全部数据
+    All,
+    // 本部门
+    Dept,
+    // 本部门及下级部门
+    DeptAndChildren,
+    // 仅本人
+    SelfOnly,
+    // 自定义部门
+    Custom,
+}
+
+impl IntoActiveValue<DataScope> for DataScope {
+    fn into_active_value(self) -> ActiveValue<DataScope> {
+        ActiveValue::Set(self)
+    }
+}
@@ -0,0 +1,100 @@
+use chrono::{Duration, Local, NaiveDateTime};
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, ActiveValue, Condition};
+
+use crate::entity::prelude::{SysApiKey, SysUser};
+use crate::entity::sys_api_key;
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::utils::crypto;
+
+use super::{Principal, TokenType};
+
+// 个人访问令牌的前缀, 用于和 jwt 区分
+pub const KEY_PREFIX: &str = "pat_";
+// 随机部分的字节数
+const KEY_BYTES: usize = 32;
+// 保存用于展示的前几位, 包含 pat_
+const DISPLAY_LEN: usize = 12;
+// 最近使用时间的更新间隔(秒), 避免每个请求都写库
+const TOUCH_INTERVAL: i64 = 60;
+
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(KEY_PREFIX)
+}
+
+// 创建 key, 明文只在创建时返回一次
+pub async fn issue<C: ConnectionTrait>(
+    db: &C,
+    user_id: &str,
+    name: &str,
+    scopes: Vec<String>,
+    expires_at: Option<NaiveDateTime>,
+) -> ApiResult<(String, sys_api_key::Model)> {
+    let key = format!("{}{}", KEY_PREFIX, crypto::random_token(KEY_BYTES));
+    let model = sys_api_key::ActiveModel {
+        user_id: ActiveValue::Set(user_id.to_string()),
+        name: ActiveValue::Set(name.to_string()),
+        prefix: ActiveValue::Set(key[..DISPLAY_LEN].to_string()),
+        key_hash: ActiveValue::Set(crypto::sha256_hex(&key)),
+        scopes: ActiveValue::Set(scopes),
+        expires_at: ActiveValue::Set(expires_at),
+        last_used_at: ActiveValue::Set(None),
+        revoked_at: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(Local::now().naive_local()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok((key, model))
+}
+
+// 吊销用户自己的 key, 已吊销的返回 false
+pub async fn revoke<C: ConnectionTrait>(db: &C, user_id: &str, id: &str) -> ApiResult<bool> {
+    let result = SysApiKey::update_many()
+        .col_expr(sys_api_key::Column::RevokedAt, Expr::value(Local::now().naive_local()))
+        .filter(sys_api_key::Column::Id.eq(id))
+        .filter(sys_api_key::Column::UserId.eq(user_id))
+        .filter(sys_api_key::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+// 校验 key 并返回所属用户, 权限限制在 key 的授权范围内
+pub async fn authenticate<C: ConnectionTrait>(db: &C, key: &str) -> ApiResult<Principal> {
+    let api_key = SysApiKey::find()
+        .filter(sys_api_key::Column::KeyHash.eq(crypto::sha256_hex(key)))
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Unauthenticated(String::from("API key 无效")))?;
+
+    let now = Local::now().naive_local();
+    if api_key.revoked_at.is_some() || api_key.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(ApiError::Unauthenticated(String::from("API key 已失效")));
+    }
+    let user = SysUser::find_by_id(&api_key.user_id)
+        .one(db)
+        .await?
+        .filter(|user| user.enabled)
+        .ok_or_else(|| ApiError::Unauthenticated(String::from("账号不存在或已被禁用")))?;
+
+    if api_key.last_used_at.is_none_or(|last_used_at| last_used_at + Duration::seconds(TOUCH_INTERVAL) <= now) {
+        SysApiKey::update_many()
+            .col_expr(sys_api_key::Column::LastUsedAt, Expr::value(now))
+            .filter(sys_api_key::Column::Id.eq(&api_key.id))
+            .filter(
+                Condition::any()
+                    .add(sys_api_key::Column::LastUsedAt.is_null())
+                    .add(sys_api_key::Column::LastUsedAt.lt(now - Duration::seconds(TOUCH_INTERVAL))),
+            )
+            .exec(db)
+            .await?;
+    }
+
+    Ok(Principal {
+        token_type: TokenType::ApiKey,
+        scopes: Some(api_key.scopes),
+        ..Principal::new(user.id, user.name)
+    })
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use axum::extract::FromRequestParts;
+use axum::http::{request::Parts, HeaderMap};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use jsonwebtoken::get_current_timestamp;
+use rand::Rng;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::{self, auth::CaptchaKind};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::utils::captcha_image::{self, CHARSET};
+
+pub const CAPTCHA_ID_HEADER: &str = "x-captcha-id";
+pub const CAPTCHA_CODE_HEADER: &str = "x-captcha-code";
+// 未使用的验证码上限, 先清理已过期的, 仍然超出时才淘汰最早过期的; 获取接口另外按 IP 限流, 避免被刷接口挤掉正常用户的验证码
+const MAX_ENTRIES: usize = 10000;
+
+// 验证码答案保存在内存中, 只适用于单实例
+static STORE: LazyLock<Mutex<HashMap<String, Entry>>> = LazyLock::new(Default::default);
+
+#[derive(Debug)]
+struct Entry {
+    answer: String,
+    // unix 秒
+    expires_at: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Captcha {
+    // 提交时放在请求头 X-Captcha-Id 中
+    pub captcha_id: String,
+    pub kind: CaptchaKind,
+    // data:image/png;base64,...
+    pub image: String,
+    pub expires_in: u64,
+}
+
+// 生成验证码, 答案只保存在服务端
+pub fn generate() -> ApiResult<Captcha> {
+    let config = config::get().auth().captcha();
+    let (text, answer) = match config.kind() {
+        CaptchaKind::Image => {
+            let text = random_text(config.length());
+            (text.clone(), text)
+        }
+        CaptchaKind::Arithmetic => arithmetic(),
+    };
+    let png = captcha_image::render(&text)?;
+
+    let captcha_id = Uuid::new_v4().to_string();
+    let now = get_current_timestamp();
+    let entry = Entry { answer, expires_at: now + config.ttl() };
+    store_entry(&mut STORE.lock().unwrap(), captcha_id.clone(), entry, now);
+
+    Ok(Captcha {
+        captcha_id,
+        kind: config.kind(),
+        image: format!("data:image/png;base64,{}", STANDARD.encode(png)),
+        expires_in: config.ttl(),
+    })
+}
+
+fn store_entry(store: &mut HashMap<String, Entry>, captcha_id: String, entry: Entry, now: u64) {
+    store.retain(|_, entry| entry.expires_at > now);
+    if store.len() >= MAX_ENTRIES {
+        let oldest = store.iter().min_by_key(|(_, entry)| entry.expires_at).map(|(id, _)| id.clone());
+        if let Some(oldest) = oldest {
+            store.remove(&oldest);
+        }
+    }
+    store.insert(captcha_id, entry);
+}
+
+// 校验验证码, 无论是否正确都立即作废, 不能重复尝试
+pub fn verify(captcha_id: &str, code: &str) -> ApiResult<()> {
+    let entry = STORE.lock().unwrap().remove(captcha_id);
+    match entry {
+        Some(entry) if entry.expires_at > get_current_timestamp() => {
+            if entry.answer.eq_ignore_ascii_case(code.trim()) {
+                Ok(())
+            } else {
+                Err(ApiError::Biz(String::from("图形验证码错误")))
+            }
+        }
+        _ => Err(ApiError::Biz(String::from("图形验证码已过期, 请刷新后重试"))),
+    }
+}
+
+// 从请求头 X-Captcha-Id 和 X-Captcha-Code 中读取并校验
+pub fn verify_headers(headers: &HeaderMap) -> ApiResult<()> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).filter(|value| !value.is_empty());
+    match (header(CAPTCHA_ID_HEADER), header(CAPTCHA_CODE_HEADER)) {
+        (Some(captcha_id), Some(code)) => verify(captcha_id, code),
+        _ => Err(ApiError::Biz(String::from("请输入图形验证码"))),
+    }
+}
+
+/*
+* 要求请求携带正确的图形验证码, 用于注册等公开接口
+*
+* async fn register(_: CaptchaVerified, ValidJson(params): ValidJson<RegisterParams>) { ... }
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct CaptchaVerified;
+
+impl<S> FromRequestParts<S> for CaptchaVerified
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        verify_headers(&parts.headers)?;
+        Ok(CaptchaVerified)
+    }
+}
+
+fn random_text(length: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..length)
+        .map(|_| CHARSET[rng.gen_range(0..CHARSET.len())] as char)
+        .collect()
+}
+
+// 10 以内的加减乘, 减法保证结果不为负数
+fn arithmetic() -> (String, String) {
+    let mut rng = rand::thread_rng();
+    let (a, b) = (rng.gen_range(1..10), rng.gen_range(1..10));
+    let (text, answer) = match rng.gen_range(0..3) {
+        0 => (format!("{}+{}=?", a, b), a + b),
+        1 => (format!("{}-{}=?", a.max(b), a.min(b)), a.max(b) - a.min(b)),
+        _ => (format!("{}×{}=?", a, b), a * b),
+    };
+    (text, answer.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(answer: &str, expires_at: u64) -> String {
+        let captcha_id = Uuid::new_v4().to_string();
+        STORE.lock().unwrap().insert(captcha_id.clone(), Entry { answer: answer.to_string(), expires_at });
+        captcha_id
+    }
+
+    #[test]
+    fn verify_ignores_case_and_whitespace() {
+        let captcha_id = insert("aB3d", get_current_timestamp() + 60);
+        assert!(verify(&captcha_id, " Ab3D ").is_ok());
+    }
+
+    #[test]
+    fn verify_is_single_use() {
+        let captcha_id = insert("1234", get_current_timestamp() + 60);
+        assert!(verify(&captcha_id, "1234").is_ok());
+        assert!(verify(&captcha_id, "1234").is_err());
+
+        // 答错后同样作废, 不能继续猜
+        let captcha_id = insert("1234", get_current_timestamp() + 60);
+        assert!(verify(&captcha_id, "0000").is_err());
+        assert!(verify(&captcha_id, "1234").is_err());
+    }
+
+    #[test]
+    fn verify_rejects_expired_and_unknown() {
+        let captcha_id = insert("1234", get_current_timestamp() - 1);
+        assert!(verify(&captcha_id, "1234").is_err());
+        assert!(verify("unknown", "1234").is_err());
+    }
+
+    #[test]
+    fn verify_headers_requires_both_headers() {
+        let captcha_id = insert("1234", get_current_timestamp() + 60);
+        let mut headers = HeaderMap::new();
+        headers.insert(CAPTCHA_ID_HEADER, captcha_id.parse().unwrap());
+        assert!(verify_headers(&headers).is_err());
+
+        headers.insert(CAPTCHA_CODE_HEADER, "1234".parse().unwrap());
+        assert!(verify_headers(&headers).is_ok());
+    }
+
+    #[test]
+    fn store_entry_drops_expired_before_live() {
+        let now = 1000;
+        let entry = |expires_at| Entry { answer: String::from("1234"), expires_at };
+        let mut store: HashMap<String, Entry> = (0..MAX_ENTRIES - 1).map(|i| (i.to_string(), entry(now + 60))).collect();
+        store.insert(String::from("expired"), entry(now));
+
+        store_entry(&mut store, String::from("new"), entry(now + 120), now);
+        assert_eq!(store.len(), MAX_ENTRIES);
+        assert!(!store.contains_key("expired"));
+        assert!(store.contains_key("0") && store.contains_key("new"));
+
+        // 没有过期的可以清理时才淘汰最早过期的
+        store.get_mut("0").unwrap().expires_at = now + 1;
+        store_entry(&mut store, String::from("newer"), entry(now + 120), now);
+        assert_eq!(store.len(), MAX_ENTRIES);
+        assert!(!store.contains_key("0"));
+    }
+
+    #[test]
+    fn arithmetic_answer_matches_question() {
+        for _ in 0..50 {
+            let (text, answer) = arithmetic();
+            let expr = text.trim_end_matches("=?");
+            let (a, op, b) = expr
+                .char_indices()
+                .find(|(_, c)| matches!(c, '+' | '-' | '×'))
+                .map(|(i, c)| (&expr[..i], c, &expr[i + c.len_utf8()..]))
+                .unwrap();
+            let (a, b) = (a.parse::<i32>().unwrap(), b.parse::<i32>().unwrap());
+            let expected = match op {
+                '+' => a + b,
+                '-' => a - b,
+                _ => a * b,
+            };
+            assert!(expected >= 0);
+            assert_eq!(answer, expected.to_string());
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, Condition, JoinType, QuerySelect};
+
+use crate::entity::prelude::{SysDept, SysRole, SysRoleDept, SysUser};
+use crate::entity::{sys_role, sys_role_dept, sys_user_role};
+use crate::enums::DataScope;
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::utils::tree::descendant_ids;
+
+use super::Principal;
+use super::permission::{db, SUPER_ADMIN};
+
+// 当前用户可以访问的数据范围, 多个角色的范围取并集
+#[derive(Debug, Clone, Default)]
+pub struct DataPermission {
+    // 不限制
+    all: bool,
+    // 可以访问这些部门的数据
+    dept_ids: HashSet<String>,
+    // 可以访问自己的数据
+    user_id: Option<String>,
+    // 按所在部门计算的范围中最大的一个(Dept 或 DeptAndChildren)
+    dept_scope: Option<DataScope>,
+}
+
+impl DataPermission {
+
+    pub fn all() -> Self {
+        Self { all: true, ..Default::default() }
+    }
+
+    // 根据用户已启用角色的数据权限计算
+    pub async fn load<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<Self> {
+        let roles = SysRole::find()
+            .join(JoinType::InnerJoin, sys_role::Relation::SysUserRole.def())
+            .filter(sys_user_role::Column::UserId.eq(user_id))
+            .filter(sys_role::Column::Enabled.eq(true))
+            .all(db)
+            .await?;
+        if roles.iter().any(|role| role.code == SUPER_ADMIN || role.data_scope == DataScope::All) {
+            return Ok(Self::all());
+        }
+
+        let dept_id = SysUser::find_by_id(user_id)
+            .one(db)
+            .await?
+            .and_then(|user| user.dept_id);
+        let mut permission = Self::default();
+        let mut custom_role_ids = Vec::new();
+        for role in &roles {
+            match role.data_scope {
+                // 已经在上面直接返回
+                DataScope::All => {}
+                DataScope::Dept => {
+                    if let Some(dept_id) = &dept_id {
+                        permission.dept_ids.insert(dept_id.clone());
+                        permission.dept_scope.get_or_insert(DataScope::Dept);
+                    }
+                }
+                DataScope::DeptAndChildren => {
+                    if let Some(dept_id) = &dept_id {
+                        let depts = SysDept::find().all(db).await?;
+                        permission.dept_ids.extend(descendant_ids(&depts, dept_id));
+                        permission.dept_ids.insert(dept_id.clone());
+                        permission.dept_scope = Some(DataScope::DeptAndChildren);
+                    }
+                }
+                DataScope::SelfOnly => permission.user_id = Some(user_id.to_string()),
+                DataScope::Custom => custom_role_ids.push(role.id.as_str()),
+            }
+        }
+        if !custom_role_ids.is_empty() {
+            let role_depts = SysRoleDept::find()
+                .filter(sys_role_dept::Column::RoleId.is_in(custom_role_ids))
+                .all(db)
+                .await?;
+            permission.dept_ids.extend(role_depts.into_iter().map(|role_dept| role_dept.dept_id));
+        }
+
+        Ok(permission)
+    }
+
+    // 是否可以访问该部门的数据, 没有部门的数据只有不限制范围时可以访问
+    pub fn contains_dept(&self, dept_id: Option<&str>) -> bool {
+        self.all || dept_id.is_some_and(|dept_id| self.dept_ids.contains(dept_id))
+    }
+
+    /*
+    * 数据范围是否不超出当前用户的范围, 用于设置角色的数据权限和给用户分配角色
+    *
+    * 自定义范围的部门必须都在当前用户的范围内; 按所在部门计算的范围不能比当前用户自己的更大
+    */
+    pub fn covers(&self, data_scope: DataScope, dept_ids: &[String]) -> bool {
+        if self.all {
+            return true;
+        }
+        match data_scope {
+            DataScope::All => false,
+            DataScope::Custom => dept_ids.iter().all(|dept_id| self.dept_ids.contains(dept_id)),
+            DataScope::DeptAndChildren => self.dept_scope == Some(DataScope::DeptAndChildren),
+            DataScope::Dept => self.dept_scope.is_some(),
+            DataScope::SelfOnly => true,
+        }
+    }
+
+    // 生成数据过滤条件, 没有任何数据权限时不返回数据
+    //
+    // SysUser::find().filter(data_permission.condition(sys_user::Column::DeptId, sys_user::Column::Id))
+    pub fn condition<D, U>(&self, dept_column: D, user_column: U) -> Condition
+    where
+        D: ColumnTrait,
+        U: ColumnTrait,
+    {
+        if self.all {
+            return Condition::all();
+        }
+
+        let mut condition = Condition::any();
+        if !self.dept_ids.is_empty() {
+            condition = condition.add(dept_column.is_in(&self.dept_ids));
+        }
+        if let Some(user_id) = &self.user_id {
+            condition = condition.add(user_column.eq(user_id));
+        }
+        if self.dept_ids.is_empty() && self.user_id.is_none() {
+            condition = condition.add(Expr::value(false));
+        }
+        condition
+    }
+}
+
+// 需要登录, 同一请求中只计算一次
+impl<S> FromRequestParts<S> for DataPermission
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(data_permission) = parts.extensions.get::<DataPermission>() {
+            return Ok(data_permission.clone());
+        }
+
+        let principal = Principal::from_request_parts(parts, state).await?;
+        let data_permission = DataPermission::load(db(), &principal.id).await?;
+
+        parts.extensions.insert(data_permission.clone());
+        Ok(data_permission)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dept(dept_scope: DataScope, dept_ids: &[&str]) -> DataPermission {
+        DataPermission {
+            dept_ids: dept_ids.iter().map(|id| String::from(*id)).collect(),
+            dept_scope: Some(dept_scope),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn covers_nothing_wider_than_self() {
+        let permission = dept(DataScope::Dept, &["2"]);
+        assert!(!permission.covers(DataScope::All, &[]));
+        assert!(!permission.covers(DataScope::DeptAndChildren, &[]));
+        assert!(!permission.covers(DataScope::Custom, &[String::from("2"), String::from("3")]));
+        assert!(permission.covers(DataScope::Custom, &[String::from("2")]));
+        assert!(permission.covers(DataScope::Dept, &[]));
+        assert!(permission.covers(DataScope::SelfOnly, &[]));
+    }
+
+    #[test]
+    fn self_only_covers_only_self() {
+        let permission = DataPermission { user_id: Some(String::from("1")), ..Default::default() };
+        assert!(permission.covers(DataScope::SelfOnly, &[]));
+        assert!(!permission.covers(DataScope::Dept, &[]));
+        assert!(!permission.covers(DataScope::Custom, &[String::from("2")]));
+        assert!(permission.covers(DataScope::Custom, &[]));
+    }
+
+    #[test]
+    fn all_covers_everything() {
+        let permission = DataPermission::all();
+        assert!(permission.covers(DataScope::All, &[]));
+        assert!(permission.covers(DataScope::Custom, &[String::from("9")]));
+        assert!(dept(DataScope::DeptAndChildren, &["2"]).covers(DataScope::DeptAndChildren, &[]));
+    }
+}
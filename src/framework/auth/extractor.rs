@@ -0,0 +1,139 @@
+use axum::extract::FromRequestParts;
+use axum::http::{header, request::Parts};
+use axum::middleware::{from_extractor, FromExtractorLayer};
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind};
+
+use crate::config;
+use crate::framework::error::ApiError;
+
+use super::{api_key, get_jwt, permission::db, revocation, session, Claims, Principal, TokenType};
+
+const BEARER: &str = "Bearer ";
+const API_KEY_HEADER: &str = "x-api-key";
+
+/*
+* 从请求中抽取 token 的声明
+*
+* 依次读取 Authorization: Bearer <token> 与配置的 cookie,
+* 校验签名后再检查是否已被吊销、会话是否已被踢下线, 通过后放入 extensions, 同一请求中再次抽取时直接复用
+*/
+impl<S> FromRequestParts<S> for Claims
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(claims) = parts.extensions.get::<Claims>() {
+            return Ok(claims.clone());
+        }
+
+        let token = extract_token(parts)
+            .ok_or_else(|| ApiError::Jwt(JwtError::from(ErrorKind::InvalidToken)))?;
+        let claims = get_jwt().decode_claims(token)?;
+        if claims.principal.token_type != TokenType::Access {
+            return Err(ApiError::Jwt(JwtError::from(ErrorKind::InvalidToken)));
+        }
+
+        if revocation::store().is_revoked(&claims).await? {
+            return Err(ApiError::Unauthenticated(String::from("登录已失效, 请重新登录")));
+        }
+        session::check(db(), &claims).await?;
+
+        parts.extensions.insert(claims.clone());
+        Ok(claims)
+    }
+}
+
+/*
+* 从请求中抽取登录主体
+*
+* 请求头 X-Api-Key 或 Authorization: Bearer pat_... 使用 API key 认证, 其余按 jwt 处理
+*/
+impl<S> FromRequestParts<S> for Principal
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(principal) = parts.extensions.get::<Principal>() {
+            return Ok(principal.clone());
+        }
+
+        let principal = match api_key_token(parts) {
+            Some(key) => api_key::authenticate(db(), &key).await?,
+            None => Claims::from_request_parts(parts, state).await?.principal,
+        };
+
+        parts.extensions.insert(principal.clone());
+        Ok(principal)
+    }
+}
+
+// 要求登录的路由层, 通过 Router::route_layer 挂载到需要保护的路由上
+pub fn require_login() -> FromExtractorLayer<Principal, ()> {
+    from_extractor::<Principal>()
+}
+
+// 用户本人登录的主体, 不接受 API key
+pub struct Interactive(pub Principal);
+
+impl<S> FromRequestParts<S> for Interactive
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let principal = Principal::from_request_parts(parts, state).await?;
+        if principal.token_type == TokenType::ApiKey {
+            return Err(ApiError::Forbidden(String::from("API key 不能访问该接口, 请登录后操作")));
+        }
+        Ok(Interactive(principal))
+    }
+}
+
+// 个人资料、两步验证、API key 管理等只能由用户本人操作的路由, 无论 API key 的授权范围如何都不允许访问
+//
+// Router::new().route(...).route_layer(require_interactive())
+pub fn require_interactive() -> FromExtractorLayer<Interactive, ()> {
+    from_extractor::<Interactive>()
+}
+
+fn api_key_token(parts: &Parts) -> Option<String> {
+    parts.headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .or_else(|| bearer_token(parts).filter(|token| api_key::is_api_key(token)))
+        .map(String::from)
+}
+
+fn extract_token(parts: &Parts) -> Option<&str> {
+    bearer_token(parts).or_else(|| {
+        config::get().auth().cookie().and_then(|name| cookie_token(parts, name))
+    })
+}
+
+fn bearer_token(parts: &Parts) -> Option<&str> {
+    parts.headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix(BEARER)
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+fn cookie_token<'a>(parts: &'a Parts, name: &str) -> Option<&'a str> {
+    parts.headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, value)| *key == name && !value.is_empty())
+        .map(|(_, value)| value)
+}
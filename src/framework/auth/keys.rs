@@ -0,0 +1,191 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters, EllipticCurveKeyType, Jwk,
+    KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use simple_asn1::ASN1Block;
+
+use crate::config;
+
+// 签名/校验 jwt 使用的 key
+#[derive(Clone)]
+pub struct JwtKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    // 只用于校验的旧 key 没有私钥
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    // 对外公开的公钥, 对称秘钥没有
+    jwk: Option<Jwk>,
+}
+
+impl JwtKey {
+
+    // HS256 共享秘钥
+    pub fn from_secret(secret: &[u8]) -> Self {
+        Self {
+            kid: None,
+            algorithm: Algorithm::HS256,
+            encoding: Some(EncodingKey::from_secret(secret)),
+            decoding: DecodingKey::from_secret(secret),
+            jwk: None,
+        }
+    }
+
+    // 从 PEM 加载非对称 key, 私钥可以为空(只用于校验)
+    pub fn from_pem(kid: &str, algorithm: &str, private_pem: Option<&[u8]>, public_pem: &[u8]) -> Result<Self> {
+        let alg = Algorithm::from_str(algorithm)
+            .with_context(|| format!("Unsupported jwt algorithm: {}", algorithm))?;
+
+        let (encoding, decoding, parameters) = match alg {
+            Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512
+            | Algorithm::PS256 | Algorithm::PS384 | Algorithm::PS512 => (
+                private_pem.map(EncodingKey::from_rsa_pem).transpose()?,
+                DecodingKey::from_rsa_pem(public_pem)?,
+                rsa_parameters(public_pem)?,
+            ),
+            Algorithm::ES256 | Algorithm::ES384 => (
+                private_pem.map(EncodingKey::from_ec_pem).transpose()?,
+                DecodingKey::from_ec_pem(public_pem)?,
+                ec_parameters(public_pem, alg)?,
+            ),
+            Algorithm::EdDSA => (
+                private_pem.map(EncodingKey::from_ed_pem).transpose()?,
+                DecodingKey::from_ed_pem(public_pem)?,
+                ed_parameters(public_pem)?,
+            ),
+            _ => anyhow::bail!("{} 是对称算法, 请使用 auth.jwt.secret", algorithm),
+        };
+
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(KeyAlgorithm::from_str(algorithm)?),
+                key_id: Some(kid.to_string()),
+                ..Default::default()
+            },
+            algorithm: parameters,
+        };
+
+        Ok(Self {
+            kid: Some(kid.to_string()),
+            algorithm: alg,
+            encoding,
+            decoding,
+            jwk: Some(jwk),
+        })
+    }
+
+    // 按 auth.jwt.keys 中的配置读取 PEM 文件
+    pub fn load(key_config: &config::auth::JwtKeyConfig) -> Result<Self> {
+        let read = |file: &str| {
+            std::fs::read(file).with_context(|| format!("Fail to read jwt key file: {}", file))
+        };
+        let private_pem = key_config.private_key().map(read).transpose()?;
+        let public_pem = read(key_config.public_key())?;
+
+        Self::from_pem(key_config.kid(), key_config.algorithm(), private_pem.as_deref(), &public_pem)
+            .with_context(|| format!("Fail to load jwt key: {}", key_config.kid()))
+    }
+
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn encoding(&self) -> Option<&EncodingKey> {
+        self.encoding.as_ref()
+    }
+
+    pub fn decoding(&self) -> &DecodingKey {
+        &self.decoding
+    }
+
+    pub fn jwk(&self) -> Option<&Jwk> {
+        self.jwk.as_ref()
+    }
+}
+
+impl fmt::Debug for JwtKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKey")
+            .field("kid", &self.kid)
+            .field("algorithm", &self.algorithm)
+            .field("signing", &self.encoding.is_some())
+            .finish()
+    }
+}
+
+// ======================================
+// 从公钥中取出 JWK 需要的参数
+// ======================================
+
+fn rsa_parameters(public_pem: &[u8]) -> Result<AlgorithmParameters> {
+    let pem = pem::parse(public_pem)?;
+    // PKCS#1 直接是 RSAPublicKey, PKCS#8 需要先取出 BIT STRING
+    let der = match pem.tag() {
+        "RSA PUBLIC KEY" => pem.contents().to_vec(),
+        _ => subject_public_key(pem.contents())?,
+    };
+
+    match simple_asn1::from_der(&der)?.as_slice() {
+        [ASN1Block::Sequence(_, fields)] => match fields.as_slice() {
+            [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => Ok(AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: URL_SAFE_NO_PAD.encode(n.to_bytes_be().1),
+                e: URL_SAFE_NO_PAD.encode(e.to_bytes_be().1),
+            })),
+            _ => anyhow::bail!("Invalid rsa public key"),
+        },
+        _ => anyhow::bail!("Invalid rsa public key"),
+    }
+}
+
+fn ec_parameters(public_pem: &[u8], algorithm: Algorithm) -> Result<AlgorithmParameters> {
+    let (curve, size) = match algorithm {
+        Algorithm::ES384 => (EllipticCurve::P384, 48),
+        _ => (EllipticCurve::P256, 32),
+    };
+    let point = subject_public_key(pem::parse(public_pem)?.contents())?;
+    // 未压缩的点: 0x04 || x || y
+    anyhow::ensure!(
+        point.len() == 1 + size * 2 && point[0] == 0x04,
+        "Invalid ec public key, expect an uncompressed {:?} point", curve
+    );
+
+    Ok(AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+        key_type: EllipticCurveKeyType::EC,
+        curve,
+        x: URL_SAFE_NO_PAD.encode(&point[1..=size]),
+        y: URL_SAFE_NO_PAD.encode(&point[size + 1..]),
+    }))
+}
+
+fn ed_parameters(public_pem: &[u8]) -> Result<AlgorithmParameters> {
+    let x = subject_public_key(pem::parse(public_pem)?.contents())?;
+    anyhow::ensure!(x.len() == 32, "Invalid ed25519 public key");
+
+    Ok(AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+        key_type: OctetKeyPairType::OctetKeyPair,
+        curve: EllipticCurve::Ed25519,
+        x: URL_SAFE_NO_PAD.encode(x),
+    }))
+}
+
+// SubjectPublicKeyInfo ::= SEQUENCE { algorithm AlgorithmIdentifier, subjectPublicKey BIT STRING }
+fn subject_public_key(der: &[u8]) -> Result<Vec<u8>> {
+    match simple_asn1::from_der(der)?.as_slice() {
+        [ASN1Block::Sequence(_, fields)] => match fields.as_slice() {
+            [_, ASN1Block::BitString(_, _, key)] => Ok(key.clone()),
+            _ => anyhow::bail!("Invalid public key, expect SubjectPublicKeyInfo"),
+        },
+        _ => anyhow::bail!("Invalid public key, expect SubjectPublicKeyInfo"),
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{LazyLock, Mutex};
+
+use jsonwebtoken::get_current_timestamp;
+
+use crate::config::{self, auth::LockoutConfig};
+use crate::framework::error::{ApiError, ApiResult};
+
+// 登录失败计数, 保存在内存中, 只适用于单实例
+// 账号和 IP 分别计数: 账号计数防止针对单个账号的暴力破解, IP 计数防止用同一密码尝试大量账号
+static ACCOUNTS: LazyLock<Mutex<HashMap<String, Attempts>>> = LazyLock::new(Default::default);
+static IPS: LazyLock<Mutex<HashMap<IpAddr, Attempts>>> = LazyLock::new(Default::default);
+// 忘记密码的请求次数, 防止给同一邮箱大量发送邮件, 与登录失败分开计数
+static RESET_EMAILS: LazyLock<Mutex<HashMap<String, Attempts>>> = LazyLock::new(Default::default);
+static RESET_IPS: LazyLock<Mutex<HashMap<IpAddr, Attempts>>> = LazyLock::new(Default::default);
+// 获取图形验证码的次数, 防止单个 IP 刷满验证码存储
+static CAPTCHA_IPS: LazyLock<Mutex<HashMap<IpAddr, Attempts>>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Default)]
+struct Attempts {
+    // 统计窗口内的失败次数
+    failures: u32,
+    // 统计窗口开始时间(unix 秒)
+    window_start: u64,
+    // 已经被锁定的次数, 用于计算下次锁定时长
+    locks: u32,
+    locked_until: u64,
+    last_failure: u64,
+}
+
+impl Attempts {
+    fn retry_after(&self, now: u64) -> Option<u64> {
+        (self.locked_until > now).then(|| self.locked_until - now)
+    }
+
+    // 记录一次失败, 达到上限时锁定并返回锁定时长
+    fn fail(&mut self, now: u64, max_attempts: u32, config: &LockoutConfig) -> Option<u64> {
+        if now >= self.window_start + config.window() {
+            self.failures = 0;
+            self.window_start = now;
+        }
+        self.failures += 1;
+        self.last_failure = now;
+        if self.failures < max_attempts {
+            return None;
+        }
+
+        let duration = config
+            .lock_duration()
+            .saturating_mul(1 << self.locks.min(16))
+            .min(config.max_lock_duration());
+        self.locks += 1;
+        self.failures = 0;
+        self.locked_until = now + duration;
+        Some(duration)
+    }
+
+    // 锁定已结束且长时间没有失败, 不再需要保留
+    fn is_stale(&self, now: u64, config: &LockoutConfig) -> bool {
+        self.locked_until <= now && self.last_failure + config.max_lock_duration().max(config.window()) <= now
+    }
+}
+
+fn locked_error(retry_after: u64) -> ApiError {
+    ApiError::TooManyAttempts {
+        message: format!("登录失败次数过多, 请 {} 秒后再试", retry_after),
+        retry_after,
+    }
+}
+
+// 登录前检查账号和 IP 是否处于锁定中
+pub fn check(account: &str, ip: IpAddr) -> ApiResult<()> {
+    let now = get_current_timestamp();
+    let account_retry = ACCOUNTS.lock().unwrap().get(account).and_then(|attempts| attempts.retry_after(now));
+    let ip_retry = IPS.lock().unwrap().get(&ip).and_then(|attempts| attempts.retry_after(now));
+
+    match account_retry.max(ip_retry) {
+        Some(retry_after) => Err(locked_error(retry_after)),
+        None => Ok(()),
+    }
+}
+
+// 记录一次登录失败, 触发锁定时返回锁定错误
+pub fn record_failure(account: &str, ip: IpAddr) -> ApiResult<()> {
+    let config = config::get().auth().lockout();
+    let now = get_current_timestamp();
+
+    let account_lock = {
+        let mut accounts = ACCOUNTS.lock().unwrap();
+        accounts.retain(|_, attempts| !attempts.is_stale(now, config));
+        accounts.entry(account.to_string()).or_default().fail(now, config.max_attempts(), config)
+    };
+    let ip_lock = {
+        let mut ips = IPS.lock().unwrap();
+        ips.retain(|_, attempts| !attempts.is_stale(now, config));
+        ips.entry(ip).or_default().fail(now, config.ip_max_attempts(), config)
+    };
+
+    if account_lock.is_some() {
+        tracing::warn!("account locked: {}", account);
+    }
+    if ip_lock.is_some() {
+        tracing::warn!("ip locked: {}", ip);
+    }
+    match account_lock.max(ip_lock) {
+        Some(retry_after) => Err(locked_error(retry_after)),
+        None => Ok(()),
+    }
+}
+
+// 登录成功后清除账号的失败记录, IP 的计数继续保留
+pub fn record_success(account: &str) {
+    ACCOUNTS.lock().unwrap().remove(account);
+}
+
+// 忘记密码的请求限流: 超过次数后在限制时长内直接拒绝, 达到上限的这一次仍然放行
+pub fn throttle_reset(email: &str, ip: IpAddr) -> ApiResult<()> {
+    let config = config::get().auth().lockout();
+    let reset = config::get().auth().reset_password();
+    let now = get_current_timestamp();
+    let email = email.to_lowercase();
+
+    let mut emails = RESET_EMAILS.lock().unwrap();
+    let mut ips = RESET_IPS.lock().unwrap();
+    let email_retry = emails.get(&email).and_then(|attempts| attempts.retry_after(now));
+    let ip_retry = ips.get(&ip).and_then(|attempts| attempts.retry_after(now));
+    if let Some(retry_after) = email_retry.max(ip_retry) {
+        return Err(ApiError::TooManyAttempts {
+            message: format!("请求过于频繁, 请 {} 秒后再试", retry_after),
+            retry_after,
+        });
+    }
+
+    emails.retain(|_, attempts| !attempts.is_stale(now, config));
+    ips.retain(|_, attempts| !attempts.is_stale(now, config));
+    if emails.entry(email.clone()).or_default().fail(now, reset.max_requests(), config).is_some() {
+        tracing::warn!("forgot password throttled, email: {}", email);
+    }
+    if ips.entry(ip).or_default().fail(now, reset.ip_max_requests(), config).is_some() {
+        tracing::warn!("forgot password throttled, ip: {}", ip);
+    }
+    Ok(())
+}
+
+// 获取图形验证码的限流, 规则与忘记密码相同
+pub fn throttle_captcha(ip: IpAddr) -> ApiResult<()> {
+    let config = config::get().auth().lockout();
+    let max_requests = config::get().auth().captcha().ip_max_requests();
+    let now = get_current_timestamp();
+
+    let mut ips = CAPTCHA_IPS.lock().unwrap();
+    if let Some(retry_after) = ips.get(&ip).and_then(|attempts| attempts.retry_after(now)) {
+        return Err(ApiError::TooManyAttempts {
+            message: format!("请求过于频繁, 请 {} 秒后再试", retry_after),
+            retry_after,
+        });
+    }
+
+    ips.retain(|_, attempts| !attempts.is_stale(now, config));
+    if ips.entry(ip).or_default().fail(now, max_requests, config).is_some() {
+        tracing::warn!("captcha throttled, ip: {}", ip);
+    }
+    Ok(())
+}
+
+// 管理员解除账号锁定
+pub fn unlock(account: &str) {
+    ACCOUNTS.lock().unwrap().remove(account);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 默认配置: 5 次失败锁定, 窗口 900 秒, 首次锁定 60 秒, 上限 3600 秒
+    fn fail_times(attempts: &mut Attempts, now: u64, times: u32, config: &LockoutConfig) -> Option<u64> {
+        (0..times).map(|_| attempts.fail(now, config.max_attempts(), config)).last().flatten()
+    }
+
+    #[test]
+    fn locks_after_max_attempts() {
+        let config = LockoutConfig::default();
+        let mut attempts = Attempts::default();
+        assert_eq!(fail_times(&mut attempts, 1000, 4, &config), None);
+        assert_eq!(attempts.retry_after(1000), None);
+        assert_eq!(attempts.fail(1000, config.max_attempts(), &config), Some(60));
+        assert_eq!(attempts.retry_after(1030), Some(30));
+        assert_eq!(attempts.retry_after(1060), None);
+    }
+
+    #[test]
+    fn lock_duration_doubles_up_to_max() {
+        let config = LockoutConfig::default();
+        let mut attempts = Attempts::default();
+        let mut now = 1000;
+        let mut durations = Vec::new();
+        for _ in 0..8 {
+            let duration = fail_times(&mut attempts, now, 5, &config).unwrap();
+            durations.push(duration);
+            now += duration;
+        }
+        assert_eq!(durations, [60, 120, 240, 480, 960, 1920, 3600, 3600]);
+    }
+
+    #[test]
+    fn failures_reset_after_window() {
+        let config = LockoutConfig::default();
+        let mut attempts = Attempts::default();
+        assert_eq!(fail_times(&mut attempts, 1000, 4, &config), None);
+        // 窗口结束后重新计数
+        assert_eq!(fail_times(&mut attempts, 1000 + 900, 4, &config), None);
+        assert_eq!(attempts.failures, 4);
+    }
+
+    #[test]
+    fn stale_after_lock_and_quiet_period() {
+        let config = LockoutConfig::default();
+        let mut attempts = Attempts::default();
+        fail_times(&mut attempts, 1000, 5, &config);
+        assert!(!attempts.is_stale(1060, &config));
+        assert!(attempts.is_stale(1000 + 3600, &config));
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use chrono::Local;
+use sea_orm::{prelude::*, ActiveValue};
+
+use crate::config;
+use crate::entity::{prelude::SysLoginLog, sys_login_log};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::request::client::ClientInfo;
+
+pub const LOGIN_SUCCESS: &str = "登录成功";
+// 密码校验通过, 等待提交两步验证码
+pub const MFA_REQUIRED: &str = "需要两步验证";
+
+// 自动清理的间隔
+const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// 启动后台清理任务, 每天删除超过保留天数的登录日志
+pub fn init(db: DatabaseConnection) {
+    let days = config::get().audit().login_log_retention_days();
+    if days == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            match purge(&db, days).await {
+                Ok(deleted) => tracing::info!("purge login logs older than {} days: {}", days, deleted),
+                Err(e) => tracing::error!("Fail to purge login logs: {:?}", e),
+            }
+        }
+    });
+}
+
+// 记录一次登录尝试, 写入失败只打印日志, 不影响登录结果
+pub async fn record<C: ConnectionTrait>(db: &C, account: &str, client: &ClientInfo, outcome: Result<&str, &ApiError>) {
+    let (success, message) = match outcome {
+        Ok(message) => (true, message.to_string()),
+        Err(e) => (false, e.to_string()),
+    };
+    let user_agent = client.parse_user_agent();
+    let model = sys_login_log::ActiveModel {
+        account: ActiveValue::Set(truncate(account, 64)),
+        success: ActiveValue::Set(success),
+        message: ActiveValue::Set(truncate(&message, 255)),
+        ip: ActiveValue::Set(client.ip.to_string()),
+        user_agent: ActiveValue::Set(client.user_agent.as_deref().map(|value| truncate(value, 512))),
+        browser: ActiveValue::Set(user_agent.browser.map(|value| truncate(&value, 64))),
+        os: ActiveValue::Set(user_agent.os.map(|value| truncate(&value, 64))),
+        created_at: ActiveValue::Set(Local::now().naive_local()),
+        ..Default::default()
+    };
+    if let Err(e) = model.insert(db).await {
+        tracing::error!("Fail to record login log of {}: {:?}", account, e);
+    }
+}
+
+// 删除 days 天之前的登录日志
+pub async fn purge<C: ConnectionTrait>(db: &C, days: u32) -> ApiResult<u64> {
+    let before = Local::now().naive_local() - chrono::Duration::days(days as i64);
+    let result = SysLoginLog::delete_many()
+        .filter(sys_login_log::Column::CreatedAt.lt(before))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+// 按字符截断, 与表字段长度一致
+fn truncate(value: &str, len: usize) -> String {
+    value.chars().take(len).collect()
+}
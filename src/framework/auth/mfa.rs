@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use chrono::Local;
+use jsonwebtoken::get_current_timestamp;
+use jsonwebtoken::errors::{Error as JwtError, ErrorKind};
+use rand::{distributions::Alphanumeric, Rng};
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, ActiveValue, TransactionTrait};
+use serde::Serialize;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::config;
+use crate::entity::prelude::{SysMfaRecoveryCode, SysUserMfa};
+use crate::entity::{sys_mfa_recovery_code, sys_user, sys_user_mfa};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::utils::{crypto, generator::next_id};
+
+use super::{get_jwt, revocation, Claims, Principal, TokenType};
+
+// RFC 6238 推荐参数, 主流验证器 App 只支持这一组
+const DIGITS: usize = 6;
+const STEP: u64 = 30;
+
+// 开启两步验证时返回给用户扫码
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Enrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+    // data:image/png;base64,...
+    pub qr_code: String,
+}
+
+fn totp(secret: &str, account: &str) -> ApiResult<TOTP> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| anyhow::anyhow!("Invalid totp secret: {:?}", e))?;
+    let totp = TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        config::get().auth().mfa().skew(),
+        STEP,
+        secret,
+        Some(config::get().auth().mfa().issuer().to_string()),
+        account.to_string(),
+    )
+    .map_err(|e| anyhow::anyhow!("Fail to create totp: {}", e))?;
+    Ok(totp)
+}
+
+// 返回验证码匹配的时间步, 前后允许 skew 个时间步的偏差
+fn matched_step(totp: &TOTP, code: &str) -> Option<u64> {
+    step_at(totp, code, get_current_timestamp(), config::get().auth().mfa().skew() as u64)
+}
+
+fn step_at(totp: &TOTP, code: &str, now: u64, skew: u64) -> Option<u64> {
+    let current = now / STEP;
+    (current.saturating_sub(skew)..=current + skew).find(|step| totp.generate(step * STEP) == code)
+}
+
+pub async fn find<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<Option<sys_user_mfa::Model>> {
+    Ok(SysUserMfa::find_by_id(user_id).one(db).await?)
+}
+
+pub async fn is_enabled<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<bool> {
+    Ok(find(db, user_id).await?.is_some_and(|mfa| mfa.enabled))
+}
+
+// 生成新的秘钥, 确认之前不生效; 已启用时需要先关闭
+pub async fn setup<C: ConnectionTrait>(db: &C, user: &sys_user::Model) -> ApiResult<Enrollment> {
+    let existed = find(db, &user.id).await?;
+    if existed.as_ref().is_some_and(|mfa| mfa.enabled) {
+        return Err(ApiError::Biz(String::from("已开启两步验证, 请先关闭")));
+    }
+
+    let secret = Secret::generate_secret().to_encoded().to_string();
+    let totp = totp(&secret, &user.account)?;
+    let qr_code = totp.get_qr_base64().map_err(|e| anyhow::anyhow!("Fail to draw qr code: {}", e))?;
+
+    let now = Local::now().naive_local();
+    let model = sys_user_mfa::ActiveModel {
+        user_id: ActiveValue::Set(user.id.clone()),
+        secret: ActiveValue::Set(secret.clone()),
+        enabled: ActiveValue::Set(false),
+        last_used_step: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+    };
+    match existed {
+        Some(_) => model.update(db).await?,
+        None => model.insert(db).await?,
+    };
+
+    Ok(Enrollment {
+        secret,
+        otpauth_uri: totp.get_url(),
+        qr_code: format!("data:image/png;base64,{}", qr_code),
+    })
+}
+
+// 使用第一个验证码确认开启, 返回恢复码
+pub async fn confirm(db: &DatabaseConnection, user: &sys_user::Model, code: &str) -> ApiResult<Vec<String>> {
+    let mfa = find(db, &user.id).await?
+        .ok_or_else(|| ApiError::Biz(String::from("请先生成两步验证秘钥")))?;
+    if mfa.enabled {
+        return Err(ApiError::Biz(String::from("已开启两步验证")));
+    }
+    let step = matched_step(&totp(&mfa.secret, &user.account)?, code)
+        .ok_or_else(invalid_code)?;
+
+    let txn = db.begin().await?;
+    let mut active_model: sys_user_mfa::ActiveModel = mfa.into();
+    active_model.enabled = ActiveValue::Set(true);
+    active_model.last_used_step = ActiveValue::Set(Some(step as i64));
+    active_model.updated_at = ActiveValue::Set(Local::now().naive_local());
+    active_model.update(&txn).await?;
+    let codes = regenerate_recovery_codes(&txn, &user.id).await?;
+    txn.commit().await?;
+
+    Ok(codes)
+}
+
+// 校验 TOTP 验证码或恢复码, 通过后验证码和恢复码都不能再次使用
+pub async fn verify<C: ConnectionTrait>(db: &C, user: &sys_user::Model, code: &str) -> ApiResult<bool> {
+    let Some(mfa) = find(db, &user.id).await?.filter(|mfa| mfa.enabled) else {
+        return Ok(false);
+    };
+
+    let code = code.trim();
+    if code.len() == DIGITS && code.chars().all(|c| c.is_ascii_digit()) {
+        let Some(step) = matched_step(&totp(&mfa.secret, &user.account)?, code) else {
+            return Ok(false);
+        };
+        // 只接受比上次更新的时间步, 并发提交同一个验证码时只有一个成功
+        let result = SysUserMfa::update_many()
+            .col_expr(sys_user_mfa::Column::LastUsedStep, Expr::value(step as i64))
+            .filter(sys_user_mfa::Column::UserId.eq(&user.id))
+            .filter(
+                sea_orm::Condition::any()
+                    .add(sys_user_mfa::Column::LastUsedStep.is_null())
+                    .add(sys_user_mfa::Column::LastUsedStep.lt(step as i64)),
+            )
+            .exec(db)
+            .await?;
+        return Ok(result.rows_affected > 0);
+    }
+
+    let result = SysMfaRecoveryCode::update_many()
+        .col_expr(sys_mfa_recovery_code::Column::UsedAt, Expr::value(Local::now().naive_local()))
+        .filter(sys_mfa_recovery_code::Column::UserId.eq(&user.id))
+        .filter(sys_mfa_recovery_code::Column::CodeHash.eq(hash_recovery_code(code)))
+        .filter(sys_mfa_recovery_code::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    if result.rows_affected > 0 {
+        tracing::info!("mfa recovery code used: {}", user.account);
+    }
+    Ok(result.rows_affected > 0)
+}
+
+// 重新生成恢复码, 之前的恢复码全部作废
+pub async fn regenerate_recovery_codes<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<Vec<String>> {
+    SysMfaRecoveryCode::delete_many()
+        .filter(sys_mfa_recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let codes: Vec<String> = (0..config::get().auth().mfa().recovery_codes())
+        .map(|_| generate_recovery_code())
+        .collect();
+    let now = Local::now().naive_local();
+    // insert_many 不会调用 before_save, 需要自己填充 id
+    SysMfaRecoveryCode::insert_many(codes.iter().map(|code| sys_mfa_recovery_code::ActiveModel {
+        id: ActiveValue::Set(next_id()),
+        user_id: ActiveValue::Set(user_id.to_string()),
+        code_hash: ActiveValue::Set(hash_recovery_code(code)),
+        used_at: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(now),
+    }))
+    .exec(db)
+    .await?;
+
+    Ok(codes)
+}
+
+// 剩余可用的恢复码数量
+pub async fn remaining_recovery_codes<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<u64> {
+    Ok(SysMfaRecoveryCode::find()
+        .filter(sys_mfa_recovery_code::Column::UserId.eq(user_id))
+        .filter(sys_mfa_recovery_code::Column::UsedAt.is_null())
+        .count(db)
+        .await?)
+}
+
+// 关闭两步验证, 同时删除恢复码
+pub async fn disable<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<()> {
+    SysUserMfa::delete_by_id(user_id).exec(db).await?;
+    SysMfaRecoveryCode::delete_many()
+        .filter(sys_mfa_recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+// 登录第一步通过后签发的临时 token
+pub fn issue_challenge(user: &sys_user::Model) -> ApiResult<String> {
+    let ttl = Duration::from_secs(config::get().auth().mfa().token_ttl());
+    Ok(get_jwt().encode_with_ttl(
+        Principal {
+            token_type: TokenType::Mfa,
+            ..Principal::new(&user.id, &user.name)
+        },
+        ttl,
+    )?)
+}
+
+// 解析临时 token, 已使用过的 token 不能再次使用
+pub async fn decode_challenge(token: &str) -> ApiResult<Claims> {
+    let claims = get_jwt().decode_claims(token)?;
+    if claims.principal.token_type != TokenType::Mfa {
+        return Err(ApiError::Jwt(JwtError::from(ErrorKind::InvalidToken)));
+    }
+    if revocation::store().is_revoked(&claims).await? {
+        return Err(ApiError::Unauthenticated(String::from("两步验证已失效, 请重新登录")));
+    }
+    Ok(claims)
+}
+
+// 恢复码格式 xxxxx-xxxxx, 忽略大小写
+fn generate_recovery_code() -> String {
+    let raw: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(|c| (c as char).to_ascii_lowercase())
+        .collect();
+    format!("{}-{}", &raw[..5], &raw[5..])
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    crypto::sha256_hex(&code.trim().to_ascii_lowercase())
+}
+
+fn invalid_code() -> ApiError {
+    ApiError::Biz(String::from("验证码错误"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 附录 B 中 SHA1 的测试秘钥
+    fn test_totp() -> TOTP {
+        TOTP::new_unchecked(Algorithm::SHA1, DIGITS, 1, STEP, b"12345678901234567890".to_vec(), None, String::new())
+    }
+
+    #[test]
+    fn matches_current_step() {
+        let totp = test_totp();
+        // RFC 6238: T = 59 时为 94287082, 取后 6 位
+        assert_eq!(totp.generate(59), "287082");
+        assert_eq!(step_at(&totp, "287082", 59, 0), Some(1));
+        assert_eq!(step_at(&totp, "000000", 59, 1), None);
+    }
+
+    #[test]
+    fn allows_skew_steps_only() {
+        let totp = test_totp();
+        let code = totp.generate(10 * STEP);
+        assert_eq!(step_at(&totp, &code, 11 * STEP, 1), Some(10));
+        assert_eq!(step_at(&totp, &code, 9 * STEP, 1), Some(10));
+        assert_eq!(step_at(&totp, &code, 11 * STEP, 0), None);
+        assert_eq!(step_at(&totp, &code, 12 * STEP, 1), None);
+    }
+
+    #[test]
+    fn skew_does_not_underflow() {
+        let totp = test_totp();
+        let code = totp.generate(0);
+        assert_eq!(step_at(&totp, &code, 0, 2), Some(0));
+    }
+
+    #[test]
+    fn recovery_code_format_and_hash() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(&code[5..6], "-");
+        assert!(code.chars().all(|c| c == '-' || c.is_ascii_lowercase() || c.is_ascii_digit()));
+        assert_eq!(hash_recovery_code(&code), hash_recovery_code(&format!(" {} ", code.to_uppercase())));
+    }
+}
@@ -1,27 +1,115 @@
-use std::{borrow::Cow, sync::LazyLock, time::Duration};
+pub mod api_key;
+pub mod captcha;
+pub mod data_scope;
+pub mod extractor;
+pub mod keys;
+pub mod lockout;
+pub mod login_log;
+pub mod mfa;
+pub mod password;
+pub mod permission;
+pub mod provider;
+pub mod refresh;
+pub mod reset;
+pub mod revocation;
+pub mod session;
 
-use jsonwebtoken::{ encode, decode, get_current_timestamp, Algorithm, DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
-use anyhow::Result;
+use std::{borrow::Cow, sync::OnceLock, time::Duration};
+
+use jsonwebtoken::{ encode, decode, decode_header, get_current_timestamp, Header, Validation};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::JwkSet;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use anyhow::{Context, Result};
+
+use crate::config;
+use crate::framework::error::{ApiError, ApiResult};
+use keys::JwtKey;
 
 const DEFAULT_SECRET: &str = "secret";
 
-static DEFAULT_JWT: LazyLock<JWT> = LazyLock::new(|| JWT::default());
+// Claims 和 Principal 已经使用的声明, 不能作为自定义声明, 否则序列化后会出现重复的 key
+const RESERVED_CLAIMS: &[&str] = &[
+    "jti", "aud", "iss", "exp", "iat", "nbf", "sub", "name", "roles", "tenant", "token_type", "sid", "scopes",
+];
+
+// 启动时根据配置初始化
+static JWT_INSTANCE: OnceLock<JWT> = OnceLock::new();
+
+// token 的用途, 只有 access token 可以访问业务接口
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenType {
+    #[default]
+    Access,
+    // 登录第一步通过后的临时 token, 只能用于提交两步验证码
+    Mfa,
+    // 通过 API key 认证, 不会出现在 jwt 中
+    ApiKey,
+}
 
 // jwt 中的主体
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Principal {
+    // 用户 id, 即 subject (主题)
+    #[serde(rename = "sub")]
     pub id: String,
+    // 显示名称
     pub name: String,
+    // 角色编码
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    // 租户
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub token_type: TokenType,
+    // 登录会话, 用于在线用户和强制下线
+    #[serde(rename = "sid", default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    // API key 的授权范围, 有值时只能使用其中的权限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+    // 业务自定义的声明
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Principal {
+    pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    // 添加自定义声明, 不允许使用保留的声明名称
+    pub fn with_claim<V: Serialize>(mut self, key: impl Into<String>, value: V) -> Result<Self> {
+        let key = key.into();
+        anyhow::ensure!(!RESERVED_CLAIMS.contains(&key.as_str()), "reserved claim: {}", key);
+        self.extra.insert(key, serde_json::to_value(value)?);
+        Ok(self)
+    }
+
+    // 读取自定义声明, 不存在或类型不符时返回 None
+    pub fn claim<V: DeserializeOwned>(&self, key: &str) -> Option<V> {
+        self.extra
+            .get(key)
+            .and_then(|value| V::deserialize(value).ok())
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
 }
 
 // jwt 中的声明
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     // jwt id (标识)
     pub jti: String,
-    // subject (主题)
-    pub sub: String,
     // audience (受众)
     pub aud: String,
     // issuer (颁发者)
@@ -30,6 +118,9 @@ pub struct Claims {
     pub exp: u64,
     // issuer at time (颁发时间)
     pub iat: u64,
+    // 主体相关的声明, 包括 subject (主题)
+    #[serde(flatten)]
+    pub principal: Principal,
 }
 
 #[derive(Debug)]
@@ -42,6 +133,10 @@ pub struct JwtConfig {
     pub issuer: String,
     // 过期时间
     pub expiration: Duration,
+    // 允许的时钟偏差
+    pub leeway: Duration,
+    // 非对称 key, 第一个用于签名, 为空时使用 secret(HS256)
+    pub keys: Vec<JwtKey>,
 }
 
 impl Default for JwtConfig {
@@ -51,19 +146,84 @@ impl Default for JwtConfig {
             audience: String::from("audience"),
             issuer: String::from("issuer"),
             expiration: Duration::from_secs(60 * 60),
+            leeway: Duration::from_secs(60),
+            keys: Vec::new(),
         }
     }
 }
 
+impl JwtConfig {
+
+    // 从 application.yml 的 auth.jwt 构建, 未配置秘钥时使用默认秘钥
+    pub fn from_config(jwt_config: &config::auth::JwtConfig) -> Result<Self> {
+        let secret = match (jwt_config.secret(), jwt_config.secret_file()) {
+            (Some(_), Some(_)) => anyhow::bail!("auth.jwt.secret 与 auth.jwt.secret_file 不能同时配置"),
+            (Some(secret), None) => Cow::Owned(secret.to_string()),
+            (None, Some(file)) => Cow::Owned(
+                std::fs::read_to_string(file)
+                    .with_context(|| format!("Fail to read jwt secret file: {}", file))?
+                    .trim()
+                    .to_string()
+            ),
+            (None, None) => Cow::Borrowed(DEFAULT_SECRET),
+        };
+        anyhow::ensure!(!secret.is_empty(), "jwt secret must not be empty");
+
+        Ok(JwtConfig {
+            secret,
+            audience: jwt_config.audience().to_string(),
+            issuer: jwt_config.issuer().to_string(),
+            expiration: Duration::from_secs(jwt_config.access_ttl()),
+            leeway: Duration::from_secs(jwt_config.leeway()),
+            keys: Self::load_keys(jwt_config)?,
+        })
+    }
+
+    // 加载 auth.jwt.keys, 并把签名用的 key 排到第一个
+    fn load_keys(jwt_config: &config::auth::JwtConfig) -> Result<Vec<JwtKey>> {
+        let mut keys = jwt_config.keys()
+            .iter()
+            .map(JwtKey::load)
+            .collect::<Result<Vec<_>>>()?;
+        if keys.is_empty() {
+            return Ok(keys);
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            anyhow::ensure!(
+                !keys[..i].iter().any(|other| other.kid() == key.kid()),
+                "Duplicate jwt kid: {}", key.kid().unwrap_or_default()
+            );
+        }
+
+        let signing = match jwt_config.kid() {
+            Some(kid) => keys.iter()
+                .position(|key| key.kid() == Some(kid))
+                .with_context(|| format!("auth.jwt.kid `{}` not found in auth.jwt.keys", kid))?,
+            None => keys.iter()
+                .position(|key| key.encoding().is_some())
+                .context("auth.jwt.keys 中没有可用于签名的私钥")?,
+        };
+        anyhow::ensure!(
+            keys[signing].encoding().is_some(),
+            "jwt key `{}` 没有配置私钥, 不能用于签名", keys[signing].kid().unwrap_or_default()
+        );
+        keys.swap(0, signing);
+
+        Ok(keys)
+    }
+
+    pub fn is_default_secret(&self) -> bool {
+        self.keys.is_empty() && self.secret == DEFAULT_SECRET
+    }
+}
+
 pub struct JWT {
-    // 秘钥jwt 结构
-    encode_secret: EncodingKey,
-    decode_secret: DecodingKey,
+    // 第一个用于签名, 其余只参与校验; 每个 key 的校验规则只允许它自己的算法
+    keys: Vec<(JwtKey, Validation)>,
 
     header: Header,
 
-    validation: Validation,
-
     expiration: Duration,
 
     audience: String,
@@ -73,21 +233,32 @@ pub struct JWT {
 
 impl JWT {
     pub fn new(config: JwtConfig) -> Self {
-        let secret = config.secret.as_bytes();
-        let header = Header::new(Algorithm::HS256);
-        // 在解析时候需要用到
-        let mut validation = Validation::new(Algorithm::HS256);
-        validation.set_audience(&[&config.audience]);
-        //
-        validation.set_issuer(&[&config.issuer]);
-        // 设置必须的字段
-        validation.set_required_spec_claims(&["jti", "sub", "aud", "iss", "iat", "exp"]);
+        let keys = if config.keys.is_empty() {
+            vec![JwtKey::from_secret(config.secret.as_bytes())]
+        } else {
+            config.keys
+        };
+
+        let mut header = Header::new(keys[0].algorithm());
+        header.kid = keys[0].kid().map(String::from);
+
+        let keys = keys.into_iter()
+            .map(|key| {
+                // 在解析时候需要用到
+                let mut validation = Validation::new(key.algorithm());
+                validation.set_audience(&[&config.audience]);
+                //
+                validation.set_issuer(&[&config.issuer]);
+                // 设置必须的字段
+                validation.set_required_spec_claims(&["jti", "sub", "aud", "iss", "iat", "exp"]);
+                validation.leeway = config.leeway.as_secs();
+                (key, validation)
+            })
+            .collect();
 
         Self {
-            encode_secret: EncodingKey::from_secret(secret),
-            decode_secret: DecodingKey::from_secret(secret),
+            keys,
             header,
-            validation,
             expiration: config.expiration,
             audience: config.audience,
             issuer: config.issuer,
@@ -95,28 +266,58 @@ impl JWT {
     }
 
     pub fn encode(&self, principal: Principal) -> Result<String> {
+        self.encode_with_ttl(principal, self.expiration)
+    }
+
+    // 指定有效时长签发, 用于临时 token
+    pub fn encode_with_ttl(&self, principal: Principal, ttl: Duration) -> Result<String> {
+        self.sign(&self.claims(principal, ttl))
+    }
+
+    // 生成待签发的声明, 需要在签发前拿到 jti 时与 sign 配合使用
+    pub fn claims(&self, principal: Principal, ttl: Duration) -> Claims {
         let now = get_current_timestamp();
-        let claims = Claims {
+        Claims {
             jti: xid::new().to_string(),
-            sub: format!("{}:{}", principal.id, principal.name), //principal.id.clone(),
             aud: self.audience.clone(),
             iss: self.issuer.clone(),
-            exp: now.saturating_add(self.expiration.as_secs()),
+            exp: now.saturating_add(ttl.as_secs()),
             iat: now,
-        };
+            principal,
+        }
+    }
+
+    pub fn sign(&self, claims: &Claims) -> Result<String> {
+        let encoding = self.keys[0].0.encoding().context("jwt signing key has no private key")?;
         Ok(
-            encode(&self.header, &claims, &self.encode_secret)?
+            encode(&self.header, claims, encoding)?
         )
     }
 
-    pub fn decode(&self, token: &str) -> Result<Principal> { 
-        let claims: Claims = decode(token, &self.decode_secret, &self.validation)?.claims;
-        let mut parts = claims.sub.splitn(2, ":");
-        let principal = Principal {
-            id: parts.next().unwrap().to_string(),
-            name: parts.next().unwrap().to_string(),
-        };
-        Ok(principal)
+    // access token 的有效时长
+    pub fn expiration(&self) -> Duration {
+        self.expiration
+    }
+
+    pub fn decode(&self, token: &str) -> ApiResult<Principal> { 
+        Ok(self.decode_claims(token)?.principal)
+    }
+
+    pub fn decode_claims(&self, token: &str) -> ApiResult<Claims> {
+        // 根据 header 中的 kid 选择校验的 key, 轮换期间新旧 key 签发的 token 都能通过
+        let kid = decode_header(token)?.kid;
+        let (key, validation) = self.keys
+            .iter()
+            .find(|(key, _)| key.kid() == kid.as_deref())
+            .ok_or_else(|| ApiError::Jwt(ErrorKind::InvalidToken.into()))?;
+        Ok(decode(token, key.decoding(), validation)?.claims)
+    }
+
+    // 所有公钥, 供其他服务校验 token
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.keys.iter().filter_map(|(key, _)| key.jwk().cloned()).collect(),
+        }
     }
 }
 
@@ -126,6 +327,46 @@ impl Default for JWT {
     }
 }
 
+// 启动时调用, 非 dev 环境下禁止使用默认秘钥
+pub fn init() -> Result<()> {
+    let app_config = config::get();
+    let jwt_config = JwtConfig::from_config(app_config.auth().jwt())?;
+    if jwt_config.is_default_secret() {
+        anyhow::ensure!(
+            app_config.is_dev(),
+            "profile `{}` 不允许使用默认的 jwt 秘钥, 请配置 auth.jwt.secret 或 auth.jwt.secret_file",
+            app_config.profile()
+        );
+        tracing::warn!("Using the default jwt secret, do not use it outside dev");
+    }
+
+    JWT_INSTANCE
+        .set(JWT::new(jwt_config))
+        .map_err(|_| anyhow::anyhow!("jwt already initialized"))
+}
+
 pub fn get_jwt() -> &'static JWT {
-    &DEFAULT_JWT
+    JWT_INSTANCE.get().expect("jwt is not initialized")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_claim_rejects_reserved_names() {
+        for name in RESERVED_CLAIMS {
+            assert!(Principal::new("1", "admin").with_claim(*name, "x").is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn custom_claim_round_trip() {
+        let principal = Principal::new("1", "admin").with_claim("dept", "d1").unwrap();
+        let json = serde_json::to_value(&principal).unwrap();
+        assert_eq!(json["dept"], "d1");
+        let principal: Principal = serde_json::from_value(json).unwrap();
+        assert_eq!(principal.claim::<String>("dept").as_deref(), Some("d1"));
+        assert!(principal.extra.get("sub").is_none());
+    }
 }
@@ -0,0 +1,86 @@
+use chrono::{Duration, Local};
+use sea_orm::{prelude::*, ActiveValue, QueryOrder, QuerySelect};
+
+use crate::config;
+use crate::entity::{prelude::SysPasswordHistory, sys_password_history, sys_user};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::utils::validation;
+
+use super::provider;
+
+// 按密码策略校验新密码, 用于无法在参数上直接校验的场景
+pub fn check_policy(password: &str, user: &sys_user::Model) -> ApiResult<()> {
+    validation::check_password(password, &[&user.account, &user.mobile_phone]).map_err(|e| {
+        ApiError::Biz(e.message.map(|message| message.to_string()).unwrap_or_else(|| e.code.to_string()))
+    })
+}
+
+pub fn hash(password: &str) -> ApiResult<String> {
+    Ok(bcrypt::hash(password, bcrypt::DEFAULT_COST)?)
+}
+
+// 新密码不能与当前密码及最近 N 次使用过的密码相同
+pub async fn ensure_not_reused<C: ConnectionTrait>(db: &C, user: &sys_user::Model, password: &str) -> ApiResult<()> {
+    let history = config::get().auth().password().history();
+    if history == 0 {
+        return Ok(());
+    }
+
+    let recent = SysPasswordHistory::find()
+        .filter(sys_password_history::Column::UserId.eq(&user.id))
+        .order_by_desc(sys_password_history::Column::CreatedAt)
+        .limit(history as u64)
+        .all(db)
+        .await?;
+    for hashed in std::iter::once(&user.password).chain(recent.iter().map(|item| &item.password)) {
+        if bcrypt::verify(password, hashed)? {
+            return Err(ApiError::Biz(format!("新密码不能与最近{}次使用过的密码相同", history)));
+        }
+    }
+    Ok(())
+}
+
+// 记录新密码的哈希, 只保留最近 N 条
+pub async fn record<C: ConnectionTrait>(db: &C, user_id: &str, hashed: &str) -> ApiResult<()> {
+    let history = config::get().auth().password().history();
+    if history == 0 {
+        return Ok(());
+    }
+
+    sys_password_history::ActiveModel {
+        user_id: ActiveValue::Set(user_id.to_string()),
+        password: ActiveValue::Set(hashed.to_string()),
+        created_at: ActiveValue::Set(Local::now().naive_local()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    let expired: Vec<String> = SysPasswordHistory::find()
+        .select_only()
+        .column(sys_password_history::Column::Id)
+        .filter(sys_password_history::Column::UserId.eq(user_id))
+        .order_by_desc(sys_password_history::Column::CreatedAt)
+        .offset(history as u64)
+        .into_tuple()
+        .all(db)
+        .await?;
+    if !expired.is_empty() {
+        SysPasswordHistory::delete_many()
+            .filter(sys_password_history::Column::Id.is_in(expired))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+// 超过配置的有效天数后必须修改密码, 外部认证的用户不受限制
+pub fn is_expired(user: &sys_user::Model) -> bool {
+    if !provider::is_local(user) {
+        return false;
+    }
+    match config::get().auth().password().max_age_days() {
+        Some(days) => user.password_updated_at + Duration::days(days as i64) <= Local::now().naive_local(),
+        None => false,
+    }
+}
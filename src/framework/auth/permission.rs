@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::middleware::{from_extractor_with_state, FromExtractorLayer};
+use sea_orm::{prelude::*, JoinType, QuerySelect};
+
+use crate::entity::prelude::{SysPermission, SysRole};
+use crate::entity::{sys_permission, sys_role, sys_role_permission, sys_user_role};
+use crate::framework::error::{ApiError, ApiResult};
+
+use super::Principal;
+
+// 内置超级管理员角色, 跳过所有权限校验
+pub const SUPER_ADMIN: &str = "super_admin";
+
+static DB: OnceLock<DatabaseConnection> = OnceLock::new();
+
+// 启动时设置查询角色、权限使用的连接
+pub fn init(db: DatabaseConnection) -> anyhow::Result<()> {
+    DB.set(db).map_err(|_| anyhow::anyhow!("permission already initialized"))
+}
+
+pub(crate) fn db() -> &'static DatabaseConnection {
+    DB.get().expect("permission is not initialized")
+}
+
+// 当前用户拥有的角色和权限
+#[derive(Debug, Clone, Default)]
+pub struct Authorities {
+    pub roles: HashSet<String>,
+    pub permissions: HashSet<String>,
+}
+
+impl Authorities {
+
+    // 从数据库加载用户已启用角色及其权限
+    pub async fn load<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<Self> {
+        let roles = SysRole::find()
+            .join(JoinType::InnerJoin, sys_role::Relation::SysUserRole.def())
+            .filter(sys_user_role::Column::UserId.eq(user_id))
+            .filter(sys_role::Column::Enabled.eq(true))
+            .all(db)
+            .await?;
+
+        let permissions = SysPermission::find()
+            .join(JoinType::InnerJoin, sys_permission::Relation::SysRolePermission.def())
+            .filter(sys_role_permission::Column::RoleId.is_in(roles.iter().map(|role| role.id.as_str())))
+            .all(db)
+            .await?;
+
+        Ok(Self {
+            roles: roles.into_iter().map(|role| role.code).collect(),
+            permissions: permissions.into_iter().map(|permission| permission.code).collect(),
+        })
+    }
+
+    // 只保留授权范围内的权限, 超级管理员也不再跳过校验
+    pub fn restrict(mut self, scopes: &[String]) -> Self {
+        self.permissions = if self.is_super_admin() {
+            scopes.iter().cloned().collect()
+        } else {
+            scopes.iter().filter(|scope| self.permissions.contains(*scope)).cloned().collect()
+        };
+        self.roles.remove(SUPER_ADMIN);
+        self
+    }
+
+    pub fn is_super_admin(&self) -> bool {
+        self.roles.contains(SUPER_ADMIN)
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.is_super_admin() || self.permissions.contains(permission)
+    }
+}
+
+// 需要登录, 同一请求中只查询一次; API key 的权限为授权范围与用户权限的交集
+impl<S> FromRequestParts<S> for Authorities
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(authorities) = parts.extensions.get::<Authorities>() {
+            return Ok(authorities.clone());
+        }
+
+        let principal = Principal::from_request_parts(parts, state).await?;
+        let mut authorities = Authorities::load(db(), &principal.id).await?;
+        if let Some(scopes) = &principal.scopes {
+            authorities = authorities.restrict(scopes);
+        }
+
+        parts.extensions.insert(authorities.clone());
+        Ok(authorities)
+    }
+}
+
+// 路由需要的权限标识
+#[derive(Debug, Clone, Copy)]
+pub struct Permission(pub &'static str);
+
+// 校验当前用户是否拥有路由需要的权限
+pub struct PermissionGuard;
+
+impl FromRequestParts<Permission> for PermissionGuard {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Permission) -> Result<Self, Self::Rejection> {
+        let authorities = Authorities::from_request_parts(parts, state).await?;
+        if authorities.has_permission(state.0) {
+            Ok(PermissionGuard)
+        } else {
+            Err(ApiError::Forbidden(format!("没有权限: {}", state.0)))
+        }
+    }
+}
+
+// 声明路由需要的权限, 通过 MethodRouter::route_layer 挂载
+//
+// routing::delete(delete_user).route_layer(require_permission("system:user:delete"))
+pub fn require_permission(permission: &'static str) -> FromExtractorLayer<PermissionGuard, Permission> {
+    from_extractor_with_state(Permission(permission))
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+use config::{Config, File, FileFormat};
+use sea_orm::prelude::async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::config::auth::FileProviderConfig;
+use crate::entity::sys_user;
+use crate::framework::error::ApiResult;
+
+use super::{dummy_verify, AuthProvider, UserInfo};
+
+/*
+* 静态用户文件, 用于系统初始化时还没有本地用户的情况, 修改文件后需要重启
+*
+* users:
+*   - account: bootstrap
+*     # bcrypt 哈希, 可以用 htpasswd -bnBC 12 "" <password> 生成
+*     password: $2b$12$...
+*     name: 初始管理员
+*     roles: [super_admin]
+*/
+pub struct FileProvider {
+    name: String,
+    users: HashMap<String, FileUser>,
+    default_roles: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsersFile {
+    #[serde(default)]
+    users: Vec<FileUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileUser {
+    account: String,
+    password: String,
+    name: Option<String>,
+    email: Option<String>,
+    mobile_phone: Option<String>,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+impl FileProvider {
+    pub fn load(config: &FileProviderConfig) -> anyhow::Result<Self> {
+        let file: UsersFile = Config::builder()
+            .add_source(File::new(config.path(), FileFormat::Yaml))
+            .build()
+            .and_then(Config::try_deserialize)
+            .with_context(|| format!("Fail to load users file: {}", config.path()))?;
+
+        let mut users = HashMap::new();
+        for user in file.users {
+            // 文件中不允许出现明文密码
+            if user.password.parse::<bcrypt::HashParts>().is_err() {
+                bail!("Password of {} in {} is not a bcrypt hash", user.account, config.path());
+            }
+            if users.contains_key(&user.account) {
+                bail!("Duplicate account {} in {}", user.account, config.path());
+            }
+            users.insert(user.account.clone(), user);
+        }
+
+        Ok(Self {
+            name: config.name().to_string(),
+            users,
+            default_roles: config.default_roles().to_vec(),
+        })
+    }
+}
+
+#[async_trait]
+impl AuthProvider for FileProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // 已经从文件中删除的账号不能再登录
+    async fn authenticate(&self, account: &str, password: &str, _user: Option<&sys_user::Model>) -> ApiResult<Option<UserInfo>> {
+        let Some(user) = self.users.get(account) else {
+            dummy_verify(password);
+            return Ok(None);
+        };
+        if !bcrypt::verify(password, &user.password)? {
+            return Ok(None);
+        }
+
+        Ok(Some(UserInfo {
+            account: None,
+            name: user.name.clone(),
+            email: user.email.clone(),
+            mobile_phone: user.mobile_phone.clone(),
+            roles: match user.roles.is_empty() {
+                true => self.default_roles.clone(),
+                false => user.roles.clone(),
+            },
+        }))
+    }
+}
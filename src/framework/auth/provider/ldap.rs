@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use ldap3::{dn_escape, ldap_escape, Ldap, LdapConnAsync, LdapConnSettings, LdapError, Scope, SearchEntry};
+use sea_orm::prelude::async_trait::async_trait;
+
+use crate::config::auth::LdapProviderConfig;
+use crate::entity::sys_user;
+use crate::framework::error::{ApiError, ApiResult};
+
+use super::{AuthProvider, UserInfo};
+
+// 密码错误等绑定失败的结果码
+const INVALID_CREDENTIALS: u32 = 49;
+
+/*
+* LDAP / Active Directory 绑定认证, 每次登录建立一个新连接
+*
+* 配置了 user_dn 时直接用账号拼出的 DN 绑定; 否则先用服务账号按 user_filter 搜索用户, 再用找到的 DN 和密码绑定
+*/
+pub struct LdapProvider {
+    config: &'static LdapProviderConfig,
+}
+
+impl LdapProvider {
+    pub fn new(config: &'static LdapProviderConfig) -> anyhow::Result<Self> {
+        if config.user_dn().is_none() && config.base_dn().is_none() {
+            bail!("LDAP provider {} requires base_dn or user_dn", config.name());
+        }
+        Ok(Self { config })
+    }
+
+    async fn bind_user(&self, ldap: &mut Ldap, account: &str, password: &str) -> ApiResult<Option<UserInfo>> {
+        let filter = self.config.user_filter().replace("{account}", &ldap_escape(account));
+        let entry = match self.config.user_dn() {
+            Some(user_dn) => {
+                let dn = user_dn.replace("{account}", &dn_escape(account));
+                if !bind(ldap, &dn, password).await? {
+                    return Ok(None);
+                }
+                // 以用户自己的身份读取属性
+                self.search(ldap, &filter).await?
+            }
+            None => {
+                if let Some(bind_dn) = self.config.bind_dn() {
+                    ldap.simple_bind(bind_dn, self.config.bind_password())
+                        .await
+                        .and_then(|result| result.success())
+                        .map_err(ldap_error)?;
+                }
+                let Some(entry) = self.search(ldap, &filter).await? else {
+                    return Ok(None);
+                };
+                if !bind(ldap, &entry.dn, password).await? {
+                    return Ok(None);
+                }
+                Some(entry)
+            }
+        };
+
+        let attributes = self.config.attributes();
+        let attribute = |name: &str| entry.as_ref().and_then(|entry| entry.attrs.get(name)).and_then(|values| values.first()).cloned();
+        Ok(Some(UserInfo {
+            account: attribute(attributes.account()),
+            name: attribute(attributes.name()),
+            email: attribute(attributes.email()),
+            mobile_phone: attribute(attributes.mobile_phone()),
+            roles: self.config.default_roles().to_vec(),
+        }))
+    }
+
+    // 没有配置 base_dn 或匹配到多个条目时视为没有找到
+    async fn search(&self, ldap: &mut Ldap, filter: &str) -> ApiResult<Option<SearchEntry>> {
+        let Some(base_dn) = self.config.base_dn() else {
+            return Ok(None);
+        };
+        let attributes = self.config.attributes();
+        let (entries, _) = ldap
+            .search(base_dn, Scope::Subtree, filter, vec![attributes.account(), attributes.name(), attributes.email(), attributes.mobile_phone()])
+            .await
+            .and_then(|result| result.success())
+            .map_err(ldap_error)?;
+        if entries.len() > 1 {
+            tracing::warn!("LDAP filter matches {} entries: {}", entries.len(), filter);
+            return Ok(None);
+        }
+        Ok(entries.into_iter().next().map(SearchEntry::construct))
+    }
+}
+
+#[async_trait]
+impl AuthProvider for LdapProvider {
+    fn name(&self) -> &str {
+        self.config.name()
+    }
+
+    async fn authenticate(&self, account: &str, password: &str, _user: Option<&sys_user::Model>) -> ApiResult<Option<UserInfo>> {
+        // 空密码会被服务端当作匿名绑定而成功
+        if password.is_empty() {
+            return Ok(None);
+        }
+
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(Duration::from_secs(self.config.timeout()))
+            .set_starttls(self.config.starttls());
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, self.config.url())
+            .await
+            .map_err(ldap_error)?;
+        ldap3::drive!(conn);
+
+        let result = self.bind_user(&mut ldap, account, password).await;
+        if let Err(e) = ldap.unbind().await {
+            tracing::warn!("Fail to unbind LDAP connection: {}", e);
+        }
+        result
+    }
+}
+
+// 绑定成功返回 true, 密码错误返回 false
+async fn bind(ldap: &mut Ldap, dn: &str, password: &str) -> ApiResult<bool> {
+    let result = ldap.simple_bind(dn, password).await.map_err(ldap_error)?;
+    if result.rc == INVALID_CREDENTIALS {
+        return Ok(false);
+    }
+    result.success().map_err(ldap_error)?;
+    Ok(true)
+}
+
+fn ldap_error(e: LdapError) -> ApiError {
+    ApiError::Internal(anyhow::anyhow!("LDAP error: {}", e))
+}
@@ -0,0 +1,27 @@
+use sea_orm::prelude::async_trait::async_trait;
+
+use crate::entity::sys_user;
+use crate::framework::error::ApiResult;
+
+use super::{dummy_verify, AuthProvider, UserInfo, LOCAL};
+
+// sys_user 中保存的 bcrypt 密码, 只认证已经存在的用户
+pub struct LocalProvider;
+
+#[async_trait]
+impl AuthProvider for LocalProvider {
+    fn name(&self) -> &str {
+        LOCAL
+    }
+
+    async fn authenticate(&self, _account: &str, password: &str, user: Option<&sys_user::Model>) -> ApiResult<Option<UserInfo>> {
+        match user {
+            Some(user) if bcrypt::verify(password, &user.password)? => Ok(Some(UserInfo::default())),
+            Some(_) => Ok(None),
+            None => {
+                dummy_verify(password);
+                Ok(None)
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+pub mod file;
+pub mod ldap;
+pub mod local;
+
+use std::collections::HashSet;
+use std::sync::{LazyLock, OnceLock};
+
+use anyhow::bail;
+use chrono::{Local, NaiveDate};
+use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::{prelude::*, ActiveValue, TransactionTrait};
+
+use crate::config::{self, auth::AuthProviderConfig};
+use crate::entity::prelude::{SysRole, SysUser, SysUserRole};
+use crate::entity::{sys_role, sys_user, sys_user_role};
+use crate::enums::Gender;
+use crate::framework::error::ApiResult;
+
+use file::FileProvider;
+use ldap::LdapProvider;
+use local::LocalProvider;
+
+// 本地密码认证的名称, 也是 sys_user.auth_source 的默认值
+pub const LOCAL: &str = "local";
+
+static PROVIDERS: OnceLock<Vec<Box<dyn AuthProvider>>> = OnceLock::new();
+
+// 账号不存在时用于比对的哈希, 与真实密码使用相同的 cost, 避免通过响应时间探测账号是否存在
+static DUMMY_HASH: LazyLock<String> = LazyLock::new(|| {
+    bcrypt::hash("dummy-password", bcrypt::DEFAULT_COST).expect("Fail to hash dummy password")
+});
+
+// 认证通过后由认证方式提供的用户信息, 用于首次登录时创建 sys_user
+#[derive(Debug, Clone, Default)]
+pub struct UserInfo {
+    // 目录中保存的账号, 目录不区分大小写时可能与输入的不同; None 表示与输入的账号一致
+    pub account: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub mobile_phone: Option<String>,
+    // 角色编码
+    pub roles: Vec<String>,
+}
+
+// 校验账号密码的方式
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    // 与 sys_user.auth_source 对应
+    fn name(&self) -> &str;
+
+    // 账号不存在或密码错误时返回 None, 无法连接等错误返回 Err
+    // user 为已经关联到该认证方式的用户, 首次登录时为 None
+    async fn authenticate(&self, account: &str, password: &str, user: Option<&sys_user::Model>) -> ApiResult<Option<UserInfo>>;
+}
+
+pub fn init() -> anyhow::Result<()> {
+    let configs = config::get().auth().providers();
+    let mut providers: Vec<Box<dyn AuthProvider>> = Vec::new();
+    if configs.is_empty() {
+        providers.push(Box::new(LocalProvider));
+    }
+    for config in configs {
+        let provider: Box<dyn AuthProvider> = match config {
+            AuthProviderConfig::Local => Box::new(LocalProvider),
+            AuthProviderConfig::Ldap(config) => Box::new(LdapProvider::new(config)?),
+            AuthProviderConfig::File(config) => Box::new(FileProvider::load(config)?),
+        };
+        providers.push(provider);
+    }
+
+    let mut names = HashSet::new();
+    for provider in &providers {
+        if !names.insert(provider.name()) {
+            bail!("Duplicate auth provider: {}", provider.name());
+        }
+    }
+    tracing::info!("auth providers: {:?}", names);
+    // 提前计算, 避免第一次比对时多一次哈希
+    LazyLock::force(&DUMMY_HASH);
+
+    PROVIDERS.set(providers).map_err(|_| anyhow::anyhow!("auth providers already initialized"))
+}
+
+fn providers() -> &'static [Box<dyn AuthProvider>] {
+    PROVIDERS.get().expect("auth providers not initialized")
+}
+
+// 账号不存在时也做一次 bcrypt 校验, 结果总是失败
+pub fn dummy_verify(password: &str) {
+    let _ = bcrypt::verify(password, &DUMMY_HASH);
+}
+
+// 密码由本系统管理, 可以修改、重置, 受密码有效期限制
+pub fn is_local(user: &sys_user::Model) -> bool {
+    user.auth_source == LOCAL
+}
+
+/*
+* 校验账号密码, 成功时返回对应的用户
+*
+* 已有的用户只使用其 auth_source 对应的认证方式, 避免外部目录中的同名账号冒用本地账号;
+* 用户不存在时按配置顺序尝试各认证方式, 第一个通过的按目录中的账号查找用户, provision 为 true 时自动创建
+* 只有登录会创建用户, 修改密码等接口传入 false
+*/
+pub async fn authenticate(db: &DatabaseConnection, account: &str, password: &str, provision: bool) -> ApiResult<Option<sys_user::Model>> {
+    let user = SysUser::find()
+        .filter(sys_user::Column::Account.eq(account))
+        .one(db)
+        .await?;
+
+    if let Some(user) = user {
+        let Some(provider) = providers().iter().find(|provider| provider.name() == user.auth_source) else {
+            tracing::warn!("auth provider not configured: {} {}", account, user.auth_source);
+            return Ok(None);
+        };
+        let info = provider.authenticate(account, password, Some(&user)).await?;
+        return Ok(info.map(|_| user));
+    }
+
+    // 某个认证方式出错时继续尝试其余的, 都没有通过时再返回错误
+    let mut error = None;
+    for provider in providers() {
+        match provider.authenticate(account, password, None).await {
+            Ok(Some(info)) => return resolve(db, provider.name(), account, info, provision).await,
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!("Fail to authenticate {} with {}: {:?}", account, provider.name(), e);
+                error = Some(e);
+            }
+        }
+    }
+    match error {
+        Some(e) => Err(e),
+        None => Ok(None),
+    }
+}
+
+// 按目录中的账号查找用户, 目录不区分大小写时同一个人只对应一个用户
+async fn resolve(db: &DatabaseConnection, auth_source: &str, account: &str, mut info: UserInfo, provision: bool) -> ApiResult<Option<sys_user::Model>> {
+    let account = info.account.take().filter(|account| !account.is_empty()).unwrap_or_else(|| account.to_string());
+    let user = SysUser::find()
+        .filter(sys_user::Column::Account.eq(&account))
+        .one(db)
+        .await?;
+    match user {
+        Some(user) if user.auth_source == auth_source => Ok(Some(user)),
+        // 账号已被其他认证方式的用户占用
+        Some(user) => {
+            tracing::warn!("account {} belongs to {}, not {}", account, user.auth_source, auth_source);
+            Ok(None)
+        }
+        None if provision => Ok(Some(create_user(db, auth_source, &account, info).await?)),
+        None => Ok(None),
+    }
+}
+
+// 外部用户首次登录, 按认证方式提供的信息创建用户并分配角色
+async fn create_user(db: &DatabaseConnection, auth_source: &str, account: &str, info: UserInfo) -> ApiResult<sys_user::Model> {
+    let name = info.name.filter(|name| !name.is_empty()).unwrap_or_else(|| account.to_string());
+    let name = unique_name(db, &name, account).await?;
+    // 邮箱是唯一的, 冲突时留空
+    let email = match info.email.filter(|email| !email.is_empty()) {
+        Some(email) if SysUser::find().filter(sys_user::Column::Email.eq(&email)).one(db).await?.is_none() => Some(email),
+        _ => None,
+    };
+    let roles = match info.roles.is_empty() {
+        true => Vec::new(),
+        false => SysRole::find()
+            .filter(sys_role::Column::Code.is_in(&info.roles))
+            .filter(sys_role::Column::Enabled.eq(true))
+            .all(db)
+            .await?,
+    };
+
+    let now = Local::now().naive_local();
+    let txn = db.begin().await?;
+    let user = sys_user::ActiveModel {
+        name: ActiveValue::Set(name),
+        gender: ActiveValue::Set(Gender::Unknown),
+        account: ActiveValue::Set(account.to_string()),
+        // 外部用户的密码不保存在本系统, 也不会用于本地认证
+        password: ActiveValue::Set(String::new()),
+        password_updated_at: ActiveValue::Set(now),
+        auth_source: ActiveValue::Set(auth_source.to_string()),
+        mobile_phone: ActiveValue::Set(info.mobile_phone.unwrap_or_default()),
+        email: ActiveValue::Set(email),
+        // 目录中通常没有生日, 用户可以登录后自行修改
+        birthday: ActiveValue::Set(NaiveDate::default()),
+        enabled: ActiveValue::Set(true),
+        dept_id: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(now),
+        updated_at: ActiveValue::Set(now),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    if !roles.is_empty() {
+        SysUserRole::insert_many(roles.iter().map(|role| sys_user_role::ActiveModel {
+            user_id: ActiveValue::Set(user.id.clone()),
+            role_id: ActiveValue::Set(role.id.clone()),
+        }))
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    tracing::info!("user provisioned by {}: {} {}", auth_source, account, user.id);
+    Ok(user)
+}
+
+// 姓名是唯一的, 冲突时依次尝试 姓名(账号)、姓名(账号)2、姓名(账号)3 ...
+async fn unique_name(db: &DatabaseConnection, name: &str, account: &str) -> ApiResult<String> {
+    let mut candidate = name.to_string();
+    let mut n = 1;
+    while SysUser::find().filter(sys_user::Column::Name.eq(&candidate)).one(db).await?.is_some() {
+        candidate = match n {
+            1 => format!("{}({})", name, account),
+            _ => format!("{}({}){}", name, account, n),
+        };
+        n += 1;
+    }
+    Ok(candidate)
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use chrono::Local;
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, ActiveValue, TransactionTrait};
+
+use crate::config;
+use crate::entity::{prelude::SysRefreshToken, sys_refresh_token};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::utils::{crypto, generator::next_id};
+
+// refresh token 的随机字节数
+const TOKEN_BYTES: usize = 32;
+
+// refresh token 的有效时长
+pub fn ttl() -> Duration {
+    Duration::from_secs(config::get().auth().jwt().refresh_ttl())
+}
+
+// 签发 refresh token, family_id 为空表示一次新的登录
+pub async fn issue<C: ConnectionTrait>(db: &C, user_id: &str, family_id: Option<String>) -> ApiResult<String> {
+    let token = crypto::random_token(TOKEN_BYTES);
+    let now = Local::now().naive_local();
+    let expires_at = now + chrono::Duration::from_std(ttl()).map_err(anyhow::Error::from)?;
+
+    sys_refresh_token::ActiveModel {
+        user_id: ActiveValue::Set(user_id.to_string()),
+        family_id: ActiveValue::Set(family_id.unwrap_or_else(next_id)),
+        token_hash: ActiveValue::Set(crypto::sha256_hex(&token)),
+        expires_at: ActiveValue::Set(expires_at),
+        used_at: ActiveValue::Set(None),
+        revoked_at: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(token)
+}
+
+// 用旧 token 换一个同 family 的新 token, 返回 (旧 token 记录, 新 token)
+//
+// 已经轮换过的 token 再次出现说明被窃取重放, 整个 family 都会被吊销
+pub async fn rotate(db: &DatabaseConnection, token: &str) -> ApiResult<(sys_refresh_token::Model, String)> {
+    let existed = SysRefreshToken::find()
+        .filter(sys_refresh_token::Column::TokenHash.eq(crypto::sha256_hex(token)))
+        .one(db)
+        .await?
+        .ok_or_else(invalid_token)?;
+
+    if existed.revoked_at.is_some() {
+        return Err(invalid_token());
+    }
+    if existed.used_at.is_some() {
+        return Err(reuse_detected(db, &existed).await);
+    }
+    let now = Local::now().naive_local();
+    if existed.expires_at <= now {
+        return Err(invalid_token());
+    }
+
+    let txn = db.begin().await?;
+    // 并发使用同一个 token 时只有一个请求能轮换成功
+    let rotated = SysRefreshToken::update_many()
+        .col_expr(sys_refresh_token::Column::UsedAt, Expr::value(now))
+        .filter(sys_refresh_token::Column::Id.eq(&existed.id))
+        .filter(sys_refresh_token::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await?;
+    if rotated.rows_affected == 0 {
+        txn.rollback().await?;
+        return Err(reuse_detected(db, &existed).await);
+    }
+    let next = issue(&txn, &existed.user_id, Some(existed.family_id.clone())).await?;
+    txn.commit().await?;
+
+    Ok((existed, next))
+}
+
+// 吊销 token 所在的 family, token 不存在时忽略
+pub async fn revoke<C: ConnectionTrait>(db: &C, token: &str) -> ApiResult<u64> {
+    let existed = SysRefreshToken::find()
+        .filter(sys_refresh_token::Column::TokenHash.eq(crypto::sha256_hex(token)))
+        .one(db)
+        .await?;
+    match existed {
+        Some(existed) => revoke_family(db, &existed.family_id).await,
+        None => Ok(0),
+    }
+}
+
+// 吊销一次登录轮换出的所有 token
+pub async fn revoke_family<C: ConnectionTrait>(db: &C, family_id: &str) -> ApiResult<u64> {
+    revoke_where(db, sys_refresh_token::Column::FamilyId.eq(family_id)).await
+}
+
+// 吊销用户所有的 refresh token
+pub async fn revoke_user<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<u64> {
+    revoke_where(db, sys_refresh_token::Column::UserId.eq(user_id)).await
+}
+
+async fn revoke_where<C: ConnectionTrait>(db: &C, condition: sea_orm::sea_query::SimpleExpr) -> ApiResult<u64> {
+    let result = SysRefreshToken::update_many()
+        .col_expr(sys_refresh_token::Column::RevokedAt, Expr::value(Local::now().naive_local()))
+        .filter(condition)
+        .filter(sys_refresh_token::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+async fn reuse_detected(db: &DatabaseConnection, token: &sys_refresh_token::Model) -> ApiError {
+    tracing::warn!("refresh token reused, revoke family: {}, user: {}", token.family_id, token.user_id);
+    match revoke_family(db, &token.family_id).await {
+        Ok(_) => invalid_token(),
+        Err(e) => e,
+    }
+}
+
+fn invalid_token() -> ApiError {
+    ApiError::Unauthenticated(String::from("refresh token 无效或已过期"))
+}
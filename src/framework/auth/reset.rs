@@ -0,0 +1,69 @@
+use chrono::Local;
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, ActiveValue};
+
+use crate::config;
+use crate::entity::{prelude::SysPasswordReset, sys_password_reset};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::utils::crypto;
+
+// 重置 token 的随机字节数
+const TOKEN_BYTES: usize = 32;
+
+// 签发找回密码的 token, 同一用户之前未使用的 token 同时作废
+pub async fn issue<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<String> {
+    let now = Local::now().naive_local();
+    SysPasswordReset::update_many()
+        .col_expr(sys_password_reset::Column::UsedAt, Expr::value(now))
+        .filter(sys_password_reset::Column::UserId.eq(user_id))
+        .filter(sys_password_reset::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+
+    let token = crypto::random_token(TOKEN_BYTES);
+    let ttl = config::get().auth().reset_password().ttl();
+    sys_password_reset::ActiveModel {
+        user_id: ActiveValue::Set(user_id.to_string()),
+        token_hash: ActiveValue::Set(crypto::sha256_hex(&token)),
+        expires_at: ActiveValue::Set(now + chrono::Duration::seconds(ttl as i64)),
+        used_at: ActiveValue::Set(None),
+        created_at: ActiveValue::Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(token)
+}
+
+// 使用 token, 返回对应的用户 id
+//
+// 需要在事务中调用, 后续修改密码失败时回滚, token 仍然可以继续使用
+pub async fn consume<C: ConnectionTrait>(db: &C, token: &str) -> ApiResult<String> {
+    let existed = SysPasswordReset::find()
+        .filter(sys_password_reset::Column::TokenHash.eq(crypto::sha256_hex(token)))
+        .one(db)
+        .await?
+        .ok_or_else(invalid_token)?;
+
+    let now = Local::now().naive_local();
+    if existed.used_at.is_some() || existed.expires_at <= now {
+        return Err(invalid_token());
+    }
+    // 并发使用同一个 token 时只有一个请求能成功
+    let consumed = SysPasswordReset::update_many()
+        .col_expr(sys_password_reset::Column::UsedAt, Expr::value(now))
+        .filter(sys_password_reset::Column::Id.eq(&existed.id))
+        .filter(sys_password_reset::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    if consumed.rows_affected == 0 {
+        return Err(invalid_token());
+    }
+
+    Ok(existed.user_id)
+}
+
+fn invalid_token() -> ApiError {
+    ApiError::Biz(String::from("重置链接无效或已过期"))
+}
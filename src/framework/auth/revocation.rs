@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Local;
+use jsonwebtoken::get_current_timestamp;
+use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::{prelude::*, ActiveValue, Condition};
+
+use crate::config::{self, auth::RevocationStoreKind};
+use crate::entity::{prelude::SysTokenRevocation, sys_token_revocation};
+use crate::framework::error::ApiResult;
+
+use super::Claims;
+
+static STORE: OnceLock<Box<dyn RevocationStore>> = OnceLock::new();
+
+// 已吊销 token 的存储, 在解析 token 之后检查
+#[async_trait]
+pub trait RevocationStore: Send + Sync {
+    // 吊销单个 token, 记录保留到 expires_at(unix 秒)
+    async fn revoke(&self, jti: &str, expires_at: u64) -> ApiResult<()>;
+
+    // 吊销用户在 issued_before 及之前签发的所有 token
+    // iat 只精确到秒, 同一秒内签发的 token 也要吊销, 否则吊销前一刻刷新得到的 token 仍然有效;
+    // 代价是吊销后同一秒内重新登录拿到的 token 也会失效, 需要再登录一次
+    async fn revoke_user(&self, user_id: &str, issued_before: u64, expires_at: u64) -> ApiResult<()>;
+
+    async fn is_revoked(&self, claims: &Claims) -> ApiResult<bool>;
+}
+
+// ======================================
+// 内存存储, 只适用于单实例
+// ======================================
+
+#[derive(Default)]
+pub struct MemoryRevocationStore {
+    // jti -> 过期时间
+    tokens: Mutex<HashMap<String, u64>>,
+    // user id -> (issued_before, 过期时间)
+    users: Mutex<HashMap<String, (u64, u64)>>,
+}
+
+#[async_trait]
+impl RevocationStore for MemoryRevocationStore {
+    async fn revoke(&self, jti: &str, expires_at: u64) -> ApiResult<()> {
+        let now = get_current_timestamp();
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.retain(|_, exp| *exp > now);
+        tokens.insert(jti.to_string(), expires_at);
+        Ok(())
+    }
+
+    async fn revoke_user(&self, user_id: &str, issued_before: u64, expires_at: u64) -> ApiResult<()> {
+        let now = get_current_timestamp();
+        let mut users = self.users.lock().unwrap();
+        users.retain(|_, (_, exp)| *exp > now);
+        users.insert(user_id.to_string(), (issued_before, expires_at));
+        Ok(())
+    }
+
+    async fn is_revoked(&self, claims: &Claims) -> ApiResult<bool> {
+        if self.tokens.lock().unwrap().contains_key(&claims.jti) {
+            return Ok(true);
+        }
+        Ok(self.users
+            .lock()
+            .unwrap()
+            .get(&claims.principal.id)
+            .is_some_and(|(issued_before, _)| claims.iat <= *issued_before))
+    }
+}
+
+// ======================================
+// 数据库存储, 多实例共享
+// ======================================
+
+pub struct DatabaseRevocationStore {
+    db: DatabaseConnection,
+}
+
+impl DatabaseRevocationStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    async fn insert(&self, jti: Option<&str>, user_id: Option<&str>, issued_before: Option<u64>, expires_at: u64) -> ApiResult<()> {
+        // 顺便清理已经没有意义的记录
+        SysTokenRevocation::delete_many()
+            .filter(sys_token_revocation::Column::ExpiresAt.lte(get_current_timestamp() as i64))
+            .exec(&self.db)
+            .await?;
+
+        sys_token_revocation::ActiveModel {
+            jti: ActiveValue::Set(jti.map(String::from)),
+            user_id: ActiveValue::Set(user_id.map(String::from)),
+            issued_before: ActiveValue::Set(issued_before.map(|t| t as i64)),
+            expires_at: ActiveValue::Set(expires_at as i64),
+            created_at: ActiveValue::Set(Local::now().naive_local()),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RevocationStore for DatabaseRevocationStore {
+    async fn revoke(&self, jti: &str, expires_at: u64) -> ApiResult<()> {
+        self.insert(Some(jti), None, None, expires_at).await
+    }
+
+    async fn revoke_user(&self, user_id: &str, issued_before: u64, expires_at: u64) -> ApiResult<()> {
+        self.insert(None, Some(user_id), Some(issued_before), expires_at).await
+    }
+
+    async fn is_revoked(&self, claims: &Claims) -> ApiResult<bool> {
+        let count = SysTokenRevocation::find()
+            .filter(
+                Condition::any()
+                    .add(sys_token_revocation::Column::Jti.eq(&claims.jti))
+                    .add(
+                        Condition::all()
+                            .add(sys_token_revocation::Column::UserId.eq(&claims.principal.id))
+                            .add(sys_token_revocation::Column::IssuedBefore.gte(claims.iat as i64)),
+                    ),
+            )
+            .filter(sys_token_revocation::Column::ExpiresAt.gt(get_current_timestamp() as i64))
+            .count(&self.db)
+            .await?;
+        Ok(count > 0)
+    }
+}
+
+// ======================================
+
+// 启动时根据 auth.revocation 初始化
+pub fn init(db: DatabaseConnection) -> anyhow::Result<()> {
+    let store: Box<dyn RevocationStore> = match config::get().auth().revocation() {
+        RevocationStoreKind::Memory => Box::new(MemoryRevocationStore::default()),
+        RevocationStoreKind::Database => Box::new(DatabaseRevocationStore::new(db)),
+    };
+    STORE
+        .set(store)
+        .map_err(|_| anyhow::anyhow!("revocation store already initialized"))
+}
+
+pub fn store() -> &'static dyn RevocationStore {
+    STORE.get().expect("revocation store is not initialized").as_ref()
+}
+
+// 吊销当前 token (退出登录)
+pub async fn revoke(claims: &Claims) -> ApiResult<()> {
+    store().revoke(&claims.jti, claims.exp).await
+}
+
+// 吊销用户已签发的所有 access token, 记录保留到最后一个 token 过期
+pub async fn revoke_user(user_id: &str) -> ApiResult<()> {
+    let now = get_current_timestamp();
+    let jwt = config::get().auth().jwt();
+    let expires_at = now + jwt.access_ttl() + jwt.leeway();
+    store().revoke_user(user_id, now, expires_at).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::auth::Principal;
+
+    fn claims(jti: &str, user_id: &str, iat: u64) -> Claims {
+        Claims {
+            jti: jti.to_string(),
+            aud: String::new(),
+            iss: String::new(),
+            exp: iat + 3600,
+            iat,
+            principal: Principal::new(user_id, user_id),
+        }
+    }
+
+    #[tokio::test]
+    async fn revoke_user_includes_same_second() {
+        let store = MemoryRevocationStore::default();
+        let now = get_current_timestamp();
+        store.revoke_user("1", now, now + 3600).await.unwrap();
+
+        assert!(store.is_revoked(&claims("a", "1", now - 1)).await.unwrap());
+        assert!(store.is_revoked(&claims("b", "1", now)).await.unwrap());
+        assert!(!store.is_revoked(&claims("c", "1", now + 1)).await.unwrap());
+        assert!(!store.is_revoked(&claims("d", "2", now)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn revoke_single_token() {
+        let store = MemoryRevocationStore::default();
+        let now = get_current_timestamp();
+        store.revoke("a", now + 3600).await.unwrap();
+
+        assert!(store.is_revoked(&claims("a", "1", now)).await.unwrap());
+        assert!(!store.is_revoked(&claims("b", "1", now)).await.unwrap());
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::{Duration, Local};
+use sea_orm::sea_query::Expr;
+use sea_orm::{prelude::*, ActiveValue, Condition};
+
+use crate::entity::{prelude::SysUserSession, sys_user_session};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::request::client::ClientInfo;
+
+use super::{refresh, revocation, Claims};
+
+// 最近访问时间的更新间隔(秒), 避免每个请求都写库
+const TOUCH_INTERVAL: i64 = 60;
+// 与表字段长度一致
+const USER_AGENT_LEN: usize = 512;
+
+// 登录成功后记录会话, 会话 id 即 access token 中的 sid
+pub async fn create<C: ConnectionTrait>(db: &C, claims: &Claims, client: &ClientInfo) -> ApiResult<()> {
+    let Some(session_id) = claims.principal.session_id.as_deref() else {
+        return Ok(());
+    };
+    let now = Local::now().naive_local();
+    sys_user_session::ActiveModel {
+        id: ActiveValue::Set(session_id.to_string()),
+        user_id: ActiveValue::Set(claims.principal.id.clone()),
+        jti: ActiveValue::Set(claims.jti.clone()),
+        ip: ActiveValue::Set(client.ip.to_string()),
+        user_agent: ActiveValue::Set(
+            client.user_agent.as_ref().map(|user_agent| user_agent.chars().take(USER_AGENT_LEN).collect())
+        ),
+        login_at: ActiveValue::Set(now),
+        last_seen_at: ActiveValue::Set(now),
+        expires_at: ActiveValue::Set(expires_at()?),
+        revoked_at: ActiveValue::Set(None),
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+// 刷新 token 后记录新的 jti, 会话有效期随 refresh token 延长
+pub async fn renew<C: ConnectionTrait>(db: &C, claims: &Claims) -> ApiResult<()> {
+    let Some(session_id) = claims.principal.session_id.as_deref() else {
+        return Ok(());
+    };
+    SysUserSession::update_many()
+        .col_expr(sys_user_session::Column::Jti, Expr::value(&claims.jti))
+        .col_expr(sys_user_session::Column::LastSeenAt, Expr::value(Local::now().naive_local()))
+        .col_expr(sys_user_session::Column::ExpiresAt, Expr::value(expires_at()?))
+        .filter(sys_user_session::Column::Id.eq(session_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+// 认证时检查会话是否已被踢下线, 顺便更新最近访问时间
+pub async fn check<C: ConnectionTrait>(db: &C, claims: &Claims) -> ApiResult<()> {
+    let Some(session_id) = claims.principal.session_id.as_deref() else {
+        return Ok(());
+    };
+    let session = SysUserSession::find_by_id(session_id)
+        .one(db)
+        .await?
+        .filter(|session| session.revoked_at.is_none())
+        .ok_or_else(|| ApiError::Unauthenticated(String::from("会话已失效, 请重新登录")))?;
+
+    let now = Local::now().naive_local();
+    if session.last_seen_at + Duration::seconds(TOUCH_INTERVAL) <= now {
+        SysUserSession::update_many()
+            .col_expr(sys_user_session::Column::LastSeenAt, Expr::value(now))
+            .filter(sys_user_session::Column::Id.eq(session_id))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+// 结束单个会话, 这次登录的 refresh token 一并吊销; 已结束的返回 false
+pub async fn revoke<C: ConnectionTrait>(db: &C, session_id: &str) -> ApiResult<bool> {
+    let result = SysUserSession::update_many()
+        .col_expr(sys_user_session::Column::RevokedAt, Expr::value(Local::now().naive_local()))
+        .filter(sys_user_session::Column::Id.eq(session_id))
+        .filter(sys_user_session::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    refresh::revoke_family(db, session_id).await?;
+    Ok(result.rows_affected > 0)
+}
+
+// 结束用户所有会话: 已签发的 access token 与 refresh token 全部失效
+pub async fn revoke_user<C: ConnectionTrait>(db: &C, user_id: &str) -> ApiResult<u64> {
+    revocation::revoke_user(user_id).await?;
+    refresh::revoke_user(db, user_id).await?;
+    let result = SysUserSession::update_many()
+        .col_expr(sys_user_session::Column::RevokedAt, Expr::value(Local::now().naive_local()))
+        .filter(sys_user_session::Column::UserId.eq(user_id))
+        .filter(sys_user_session::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+// 未结束且 refresh token 未过期的会话
+pub fn active() -> Condition {
+    Condition::all()
+        .add(sys_user_session::Column::RevokedAt.is_null())
+        .add(sys_user_session::Column::ExpiresAt.gt(Local::now().naive_local()))
+}
+
+fn expires_at() -> ApiResult<DateTime> {
+    Ok(Local::now().naive_local() + Duration::from_std(refresh::ttl()).map_err(anyhow::Error::from)?)
+}
@@ -1,6 +1,13 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use sea_orm::{ColumnTrait, Order, QueryOrder};
+use serde::de::{Deserializer, IgnoredAny, MapAccess, Visitor};
 use serde::{Deserialize, Serialize};
+use super::error::{ApiError, ApiResult};
 use super::serde::deserialize_number;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 
 
@@ -10,25 +17,156 @@ use validator::Validate;
 
 const DEFAULT_PAGE: u64 = 1;
 const DEFAULT_SIZE: u64 = 15;
+const MAX_SORTS: u64 = 5;
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Validate)]
+#[derive(Debug, Clone, PartialEq, Eq, Validate)]
 pub struct PaginationParams {
-    // 提供自定义的反序列函数
     #[validate(range(min = 1, message = "页码必须大于0"))]
-    #[serde(default = "default_page", deserialize_with = "deserialize_number")]
     pub page: u64,
 
     #[validate(range(min = 1, max = 100,message = "分页大小必须大于0"))]
-    #[serde(default = "default_size", deserialize_with = "deserialize_number")]
     pub size: u64,
+
+    // 排序, 格式为 字段名,asc|desc, 可以重复传入, 如 sort=name,asc&sort=birthday,desc
+    #[validate(length(max = MAX_SORTS, message = "排序字段不能超过5个"), custom(function = "validate_sort"))]
+    pub sort: Vec<String>,
 }
 
+impl Default for PaginationParams {
+    fn default() -> Self {
+        Self { page: DEFAULT_PAGE, size: DEFAULT_SIZE, sort: Vec::new() }
+    }
+}
 
-fn default_page() -> u64 {
-    DEFAULT_PAGE
+impl PaginationParams {
+    /*
+    * 按接口的白名单把排序参数应用到查询上, 没有传排序时使用 default, 如 "createdAt,desc"
+    *
+    * 实际使用的排序会写回 sort, 由 Page::from_pagination 返回给前端:
+    *
+    * const SORT_FIELDS: SortFields<sys_user::Column> = &[
+    *     ("name", sys_user::Column::Name),
+    *     ("createdAt", sys_user::Column::CreatedAt),
+    * ];
+    *
+    * let query = pagination.apply_sort(SysUser::find(), SORT_FIELDS, "createdAt,desc")?;
+    */
+    pub fn apply_sort<Q, C>(&mut self, mut query: Q, fields: &[(&str, C)], default: &str) -> ApiResult<Q>
+    where
+        Q: QueryOrder,
+        C: ColumnTrait,
+    {
+        if self.sort.is_empty() {
+            self.sort.push(default.to_string());
+        }
+        for sort in &self.sort {
+            let sort = sort.parse::<SortOrder>().map_err(|e| ApiError::Validation(format!("sort: {}", e)))?;
+            let column = fields
+                .iter()
+                .find(|(name, _)| *name == sort.field)
+                .map(|(_, column)| *column)
+                .ok_or_else(|| ApiError::Validation(format!("sort: 不支持的排序字段 {}", sort.field)))?;
+            query = query.order_by(column, sort.direction.into());
+        }
+        Ok(query)
+    }
 }
-fn default_size() -> u64 {
-    DEFAULT_SIZE
+
+// 手动实现反序列化: sort 可以重复出现, 派生的实现会报 duplicate field;
+// flatten 时只会拿到 page、size、sort 三个参数, 其余参数留给后面的字段
+impl<'de> Deserialize<'de> for PaginationParams {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("PaginationParams", &["page", "size", "sort"], PaginationVisitor)
+    }
+}
+
+struct PaginationVisitor;
+
+// query 参数中的数字都是字符串
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct Number(#[serde(deserialize_with = "deserialize_number")] u64);
+
+impl<'de> Visitor<'de> for PaginationVisitor {
+    type Value = PaginationParams;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("pagination params")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut params = PaginationParams::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "page" => params.page = map.next_value::<Number>()?.0,
+                "size" => params.size = map.next_value::<Number>()?.0,
+                "sort" => params.sort.push(map.next_value()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(params)
+    }
+}
+
+fn validate_sort(sort: &[String]) -> Result<(), ValidationError> {
+    for value in sort {
+        if let Err(e) = value.parse::<SortOrder>() {
+            return Err(ValidationError::new("invalid").with_message(Cow::from(e)));
+        }
+    }
+    Ok(())
+}
+
+// ======================================
+// 排序的数据结构
+// ======================================
+
+// 允许排序的字段: 参数名(与返回数据的 camelCase 字段名一致)和对应的列
+pub type SortFields<C> = &'static [(&'static str, C)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl From<SortDirection> for Order {
+    fn from(direction: SortDirection) -> Self {
+        match direction {
+            SortDirection::Asc => Order::Asc,
+            SortDirection::Desc => Order::Desc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SortOrder {
+    pub field: String,
+    pub direction: SortDirection,
+}
+
+// 字段名,asc|desc, 不传方向时按 asc 处理
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (field, direction) = match value.split_once(',') {
+            Some((field, direction)) => (field.trim(), direction.trim()),
+            None => (value.trim(), "asc"),
+        };
+        if field.is_empty() || !field.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("排序字段 {} 格式不正确", value));
+        }
+        let direction = match direction.to_ascii_lowercase().as_str() {
+            "asc" => SortDirection::Asc,
+            "desc" => SortDirection::Desc,
+            _ => return Err(format!("排序方向 {} 只能是 asc 或 desc", direction)),
+        };
+        Ok(Self { field: field.to_string(), direction })
+    }
 }
 
 // ======================================
@@ -40,16 +178,89 @@ pub struct Page<T> {
     pub page: u64,
     pub size: u64,
     pub total: u64,
-    pub items: Vec<T>
+    pub items: Vec<T>,
+    // 实际使用的排序
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sort: Vec<SortOrder>,
 }
 
 impl <T> Page<T> {
     pub fn new(page: u64, size: u64, total: u64, items: Vec<T>) -> Self {
-        Self{ page, size, total, items }
+        Self{ page, size, total, items, sort: Vec::new() }
     }
 
     pub fn from_pagination(pagination: PaginationParams, total: u64, items: Vec<T>) -> Self {
-        Self::new(pagination.page, pagination.size, total, items)
+        let mut page = Self::new(pagination.page, pagination.size, total, items);
+        page.sort = pagination.sort.iter().filter_map(|sort| sort.parse().ok()).collect();
+        page
     }
 }
 // ======================================
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, EntityTrait, QueryTrait};
+
+    use super::*;
+    use crate::entity::{prelude::SysUser, sys_user};
+
+    const SORT_FIELDS: SortFields<sys_user::Column> = &[
+        ("name", sys_user::Column::Name),
+        ("createdAt", sys_user::Column::CreatedAt),
+    ];
+
+    #[test]
+    fn sort_order_parses_field_and_direction() {
+        let sort = "createdAt, DESC".parse::<SortOrder>().unwrap();
+        assert_eq!(sort, SortOrder { field: String::from("createdAt"), direction: SortDirection::Desc });
+        assert_eq!("name".parse::<SortOrder>().unwrap().direction, SortDirection::Asc);
+
+        assert!("".parse::<SortOrder>().is_err());
+        assert!("name,up".parse::<SortOrder>().is_err());
+        assert!("name;drop,asc".parse::<SortOrder>().is_err());
+        assert!("created_at,asc".parse::<SortOrder>().is_err());
+    }
+
+    #[test]
+    fn apply_sort_uses_whitelisted_columns() {
+        let mut pagination = PaginationParams { sort: vec![String::from("name,desc"), String::from("createdAt")], ..Default::default() };
+        let sql = pagination
+            .apply_sort(SysUser::find(), SORT_FIELDS, "createdAt,desc")
+            .unwrap()
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert!(sql.ends_with(r#"ORDER BY "sys_user"."name" DESC, "sys_user"."created_at" ASC"#), "{}", sql);
+
+        let mut pagination = PaginationParams { sort: vec![String::from("password,asc")], ..Default::default() };
+        assert!(pagination.apply_sort(SysUser::find(), SORT_FIELDS, "createdAt,desc").is_err());
+    }
+
+    #[test]
+    fn apply_sort_falls_back_to_default() {
+        let mut pagination = PaginationParams::default();
+        let sql = pagination
+            .apply_sort(SysUser::find(), SORT_FIELDS, "createdAt,desc")
+            .unwrap()
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert!(sql.ends_with(r#"ORDER BY "sys_user"."created_at" DESC"#), "{}", sql);
+
+        // 实际使用的排序返回给前端
+        let page = Page::<()>::from_pagination(pagination, 0, Vec::new());
+        assert_eq!(page.sort, vec![SortOrder { field: String::from("createdAt"), direction: SortDirection::Desc }]);
+    }
+
+    #[test]
+    fn pagination_accepts_repeated_sort() {
+        let json = r#"{"page":"2","size":"20","sort":"name,desc","sort":"createdAt","keyword":"a"}"#;
+        let pagination: PaginationParams = serde_json::from_str(json).unwrap();
+        assert_eq!(pagination.page, 2);
+        assert_eq!(pagination.size, 20);
+        assert_eq!(pagination.sort, vec!["name,desc", "createdAt"]);
+        assert!(pagination.validate().is_ok());
+
+        let pagination: PaginationParams = serde_json::from_str(r#"{"sort":"name,up"}"#).unwrap();
+        assert!(pagination.validate().is_err());
+        assert!(serde_json::from_str::<PaginationParams>(r#"{"page":"x"}"#).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use axum::Json;
 use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::IntoResponse;
 use axum_valid::ValidRejection;
 use bcrypt::BcryptError;
@@ -29,10 +29,24 @@ pub enum ApiError {
     #[error("Body参数错误: {0}")]
     Json(#[from] JsonRejection),
 
+    #[error("{0}")]
+    Unauthenticated(String),
+
+    #[error("{0}")]
+    Forbidden(String),
+
+    // 密码已过期, 需要先修改密码才能登录
+    #[error("密码已过期, 请修改密码后重新登录")]
+    PasswordExpired,
+
+    // 登录失败次数过多被临时锁定, retry_after 为剩余秒数
+    #[error("{message}")]
+    TooManyAttempts { message: String, retry_after: u64 },
+
     #[error("jwt 错误: {0}")]
     Jwt(#[from] jsonwebtoken::errors::Error),
 
-    #[error("参数校验错误")]
+    #[error("参数校验错误: {0}")]
     Validation(String),
 
     #[error("密钥 Hash 错误: {0}")]
@@ -65,7 +79,9 @@ impl ApiError {
             ApiError::DatabaseErr(_) | ApiError::Bcrypt(_) | ApiError::Internal(_) => {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
-            ApiError::Jwt(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Unauthenticated(_) | ApiError::Jwt(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) | ApiError::PasswordExpired => StatusCode::FORBIDDEN,
+            ApiError::TooManyAttempts { .. } => StatusCode::TOO_MANY_REQUESTS,
             ApiError::Biz(_) => StatusCode::BAD_REQUEST,
         }
     }
@@ -75,6 +91,9 @@ impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         let status_code = self.status_code();
         let body = Json(ApiResponse::<()>::err(self.to_string()));
+        if let ApiError::TooManyAttempts { retry_after, .. } = self {
+            return (status_code, [(header::RETRY_AFTER, retry_after.to_string())], body).into_response();
+        }
         (status_code, body).into_response()
     }
 }
@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use chrono::Local;
+use lettre::message::{header::ContentType, Mailbox, Message};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use sea_orm::prelude::async_trait::async_trait;
+
+use crate::config::{self, mail::{MailConfig, MailTransportKind, SmtpTls}};
+use crate::framework::utils::generator::next_id;
+
+static MAILER: OnceLock<Box<dyn Mailer>> = OnceLock::new();
+
+// 纯文本邮件
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    fn to_message(&self, from: &str) -> anyhow::Result<Message> {
+        let from: Mailbox = from.parse().with_context(|| format!("Invalid mail from: {}", from))?;
+        let to: Mailbox = self.to.parse().with_context(|| format!("Invalid mail to: {}", self.to))?;
+        Message::builder()
+            .from(from)
+            .to(to)
+            .subject(&self.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(self.body.clone())
+            .context("Fail to build mail")
+    }
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: &Mail) -> anyhow::Result<()>;
+}
+
+// ======================================
+// 打印到日志, 用于开发环境
+// ======================================
+
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: &Mail) -> anyhow::Result<()> {
+        tracing::info!("mail to: {}, subject: {}\n{}", mail.to, mail.subject, mail.body);
+        Ok(())
+    }
+}
+
+// ======================================
+// 写入目录, 离线测试时从目录中读取邮件
+// ======================================
+
+pub struct OutboxMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+impl OutboxMailer {
+    pub fn new(from: &str, dir: impl Into<PathBuf>) -> Self {
+        Self { from: from.to_string(), dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for OutboxMailer {
+    async fn send(&self, mail: &Mail) -> anyhow::Result<()> {
+        let message = mail.to_message(&self.from)?;
+        tokio::fs::create_dir_all(&self.dir).await
+            .with_context(|| format!("Fail to create outbox dir: {}", self.dir.display()))?;
+
+        let file = self.dir.join(format!("{}-{}.eml", Local::now().format("%Y%m%d%H%M%S"), next_id()));
+        tokio::fs::write(&file, message.formatted()).await
+            .with_context(|| format!("Fail to write mail: {}", file.display()))?;
+        tracing::info!("mail to: {}, written to {}", mail.to, file.display());
+        Ok(())
+    }
+}
+
+// ======================================
+// SMTP
+// ======================================
+
+pub struct SmtpMailer {
+    from: String,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &MailConfig) -> anyhow::Result<Self> {
+        let smtp = config.smtp();
+        let mut builder = match smtp.tls() {
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp.host())?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(smtp.host())?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(smtp.host()),
+        };
+        if let Some(port) = smtp.port() {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (smtp.username(), smtp.password()) {
+            builder = builder.credentials(Credentials::new(username.to_string(), password.to_string()));
+        }
+
+        Ok(Self { from: config.from().to_string(), transport: builder.build() })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: &Mail) -> anyhow::Result<()> {
+        let message = mail.to_message(&self.from)?;
+        self.transport.send(message).await.context("Fail to send mail")?;
+        tracing::info!("mail to: {}, sent", mail.to);
+        Ok(())
+    }
+}
+
+// 按配置创建邮件发送方式, 日志中会出现重置密码链接等敏感内容, 非 dev 环境不允许打印到日志
+pub fn init() -> anyhow::Result<()> {
+    let config = config::get().mail();
+    anyhow::ensure!(
+        config.transport() != MailTransportKind::Log || config::get().is_dev(),
+        "profile `{}` 不允许使用 mail.transport: log, 请配置 outbox 或 smtp",
+        config::get().profile()
+    );
+    let mailer: Box<dyn Mailer> = match config.transport() {
+        MailTransportKind::Log => Box::new(LogMailer),
+        MailTransportKind::Outbox => Box::new(OutboxMailer::new(config.from(), config.outbox_dir())),
+        MailTransportKind::Smtp => Box::new(SmtpMailer::new(config)?),
+    };
+    MAILER.set(mailer).map_err(|_| anyhow::anyhow!("mailer already initialized"))
+}
+
+pub async fn send(mail: &Mail) -> anyhow::Result<()> {
+    MAILER.get().expect("mailer is not initialized").send(mail).await
+}
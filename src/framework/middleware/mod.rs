@@ -1,3 +1,4 @@
 
 pub mod latency;
 pub mod logger;
+pub mod oper_log;
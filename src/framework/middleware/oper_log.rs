@@ -0,0 +1,347 @@
+use std::convert::Infallible;
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use axum::body::{to_bytes, Body, Bytes};
+use axum::extract::{FromRequestParts, OriginalUri, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use chrono::Local;
+use futures_util::{stream, StreamExt};
+use sea_orm::{prelude::*, ActiveValue};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tower::{Layer, Service};
+
+use crate::config;
+use crate::entity::{prelude::SysOperLog, sys_oper_log};
+use crate::framework::auth::Principal;
+use crate::framework::error::ApiResult;
+use crate::framework::request::client::ClientInfo;
+use crate::framework::response::ApiResponse;
+
+// 与 server 中的请求体限制一致
+const MAX_BODY: usize = 10 * 1024 * 1024;
+// 只读取错误响应的开头, 足够拿到 message
+const MAX_ERROR_BODY: usize = 64 * 1024;
+// 保存的参数和错误信息的最大长度
+const MAX_TEXT: usize = 2000;
+// 字段名包含这些词时脱敏, 不区分大小写
+const SENSITIVE_FIELDS: &[&str] = &["password", "secret", "token"];
+const REDACTED: &str = "******";
+// 写入队列的长度, 写满后丢弃并打印警告
+const QUEUE_SIZE: usize = 1024;
+const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+static SENDER: OnceLock<mpsc::Sender<sys_oper_log::ActiveModel>> = OnceLock::new();
+
+// 启动后台任务: 异步写入操作日志, 每天清理超过保留天数的日志
+pub fn init(db: DatabaseConnection) -> anyhow::Result<()> {
+    let (sender, mut receiver) = mpsc::channel::<sys_oper_log::ActiveModel>(QUEUE_SIZE);
+    SENDER.set(sender).map_err(|_| anyhow::anyhow!("oper log already initialized"))?;
+
+    let writer = db.clone();
+    tokio::spawn(async move {
+        while let Some(model) = receiver.recv().await {
+            if let Err(e) = model.insert(&writer).await {
+                tracing::error!("Fail to write oper log: {:?}", e);
+            }
+        }
+    });
+
+    let days = config::get().audit().oper_log_retention_days();
+    if days > 0 {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                match purge(&db, days).await {
+                    Ok(deleted) => tracing::info!("purge oper logs older than {} days: {}", days, deleted),
+                    Err(e) => tracing::error!("Fail to purge oper logs: {:?}", e),
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+// 删除 days 天之前的操作日志
+pub async fn purge<C: ConnectionTrait>(db: &C, days: u32) -> ApiResult<u64> {
+    let before = Local::now().naive_local() - chrono::Duration::days(days as i64);
+    let result = SysOperLog::delete_many()
+        .filter(sys_oper_log::Column::CreatedAt.lt(before))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+// 接口所属的模块和操作
+#[derive(Debug, Clone, Copy)]
+pub struct Operation {
+    pub module: &'static str,
+    pub action: &'static str,
+}
+
+// 记录操作日志, 通过 MethodRouter::route_layer 挂载, 放在 require_permission 之后可以同时记录没有权限的调用
+//
+// routing::delete(delete_user)
+//     .route_layer(require_permission("system:user:delete"))
+//     .route_layer(oper_log("用户管理", "删除用户"))
+pub fn oper_log(module: &'static str, action: &'static str) -> OperLogLayer {
+    OperLogLayer { operation: Operation { module, action } }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OperLogLayer {
+    operation: Operation,
+}
+
+impl<S> Layer<S> for OperLogLayer {
+    type Service = OperLogService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OperLogService { inner, operation: self.operation }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OperLogService<S> {
+    inner: S,
+    operation: Operation,
+}
+
+impl<S> Service<Request> for OperLogService<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = std::pin::Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // 使用已经 poll_ready 的 service, 留一个新的 clone 给下一次调用
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let operation = self.operation;
+
+        Box::pin(async move {
+            let start = Instant::now();
+            let (mut parts, body) = request.into_parts();
+            let Ok(client) = ClientInfo::from_request_parts(&mut parts, &()).await;
+            // require_login 已经把登录主体放入 extensions
+            let principal = parts.extensions.get::<Principal>().cloned();
+            // 嵌套路由中 uri 已经去掉了前缀
+            let path = parts.extensions
+                .get::<OriginalUri>()
+                .map(|OriginalUri(uri)| uri.path().to_string())
+                .unwrap_or_else(|| parts.uri.path().to_string());
+            let body = match to_bytes(body, MAX_BODY).await {
+                Ok(body) => body,
+                Err(_) => return Ok(StatusCode::PAYLOAD_TOO_LARGE.into_response()),
+            };
+
+            let mut model = sys_oper_log::ActiveModel {
+                module: ActiveValue::Set(operation.module.to_string()),
+                action: ActiveValue::Set(operation.action.to_string()),
+                user_id: ActiveValue::Set(principal.as_ref().map(|principal| principal.id.clone())),
+                user_name: ActiveValue::Set(principal.map(|principal| principal.name)),
+                method: ActiveValue::Set(parts.method.to_string()),
+                path: ActiveValue::Set(truncate(&path, 255)),
+                params: ActiveValue::Set(params(parts.uri.query(), &body)),
+                ip: ActiveValue::Set(client.ip.to_string()),
+                created_at: ActiveValue::Set(Local::now().naive_local()),
+                ..Default::default()
+            };
+
+            let response = inner.call(Request::from_parts(parts, Body::from(body))).await?;
+            let status = response.status();
+            let (response, error_msg) = if status.is_success() {
+                (response, None)
+            } else {
+                error_message(response).await
+            };
+            model.status = ActiveValue::Set(status.as_u16() as i32);
+            model.success = ActiveValue::Set(status.is_success());
+            model.error_msg = ActiveValue::Set(error_msg);
+            model.latency = ActiveValue::Set(start.elapsed().as_millis() as i64);
+            send(model);
+
+            Ok(response)
+        })
+    }
+}
+
+fn send(model: sys_oper_log::ActiveModel) {
+    let Some(sender) = SENDER.get() else {
+        return;
+    };
+    if let Err(e) = sender.try_send(model) {
+        tracing::warn!("Drop oper log: {}", e);
+    }
+}
+
+// 优先记录请求体, 没有请求体时记录 query 参数, 两者都对敏感字段脱敏
+fn params(query: Option<&str>, body: &Bytes) -> Option<String> {
+    if body.is_empty() {
+        return query.map(|query| truncate(&redact_query(query), MAX_TEXT));
+    }
+    let text = match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            redact(&mut value);
+            value.to_string()
+        }
+        // 无法脱敏, 只记录长度
+        Err(_) => format!("<{} bytes>", body.len()),
+    };
+    Some(truncate(&text, MAX_TEXT))
+}
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_FIELDS.iter().any(|field| key.contains(field))
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive(key) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+// 按参数名脱敏, 其余部分保持原样
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if is_sensitive(&percent_decode(key)) => format!("{}={}", key, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// 参数名可能经过编码, 如 access%5Ftoken
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// 失败时从 ApiResponse 中取出错误信息, 只读取响应的开头, 再与剩余部分拼接, 返回给客户端的内容保持不变
+async fn error_message(response: Response) -> (Response, Option<String>) {
+    let (parts, body) = response.into_parts();
+    let mut rest = body.into_data_stream().fuse();
+    let mut head = Vec::new();
+    let mut read = 0;
+    let mut error = None;
+    while read < MAX_ERROR_BODY {
+        match rest.next().await {
+            Some(Ok(chunk)) => {
+                read += chunk.len();
+                head.push(Ok(chunk));
+            }
+            Some(Err(e)) => {
+                error = Some(e.to_string());
+                head.push(Err(e));
+                break;
+            }
+            None => break,
+        }
+    }
+
+    let bytes: Vec<u8> = head.iter().flatten().flat_map(|chunk| chunk.iter().copied()).collect();
+    let message = match serde_json::from_slice::<ApiResponse<Value>>(&bytes) {
+        Ok(response) => response.message,
+        Err(_) if bytes.is_empty() => error.map(|e| format!("Fail to read response: {}", e)).unwrap_or_default(),
+        Err(_) => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+
+    let body = Body::from_stream(stream::iter(head).chain(rest));
+    (Response::from_parts(parts, body), Some(truncate(&message, MAX_TEXT)))
+}
+
+fn truncate(value: &str, len: usize) -> String {
+    value.chars().take(len).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body_of(response: Response) -> Bytes {
+        to_bytes(response.into_body(), usize::MAX).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn error_message_reads_api_response() {
+        let body = r#"{"code":1,"message":"用户不存在"}"#;
+        let response = (StatusCode::BAD_REQUEST, body).into_response();
+        let (response, message) = error_message(response).await;
+        assert_eq!(message.as_deref(), Some("用户不存在"));
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body_of(response).await, body);
+    }
+
+    #[tokio::test]
+    async fn error_message_keeps_large_body() {
+        let body = "x".repeat(MAX_ERROR_BODY * 3);
+        let chunks = body.as_bytes().chunks(1000).map(|chunk| Ok::<_, Infallible>(Bytes::copy_from_slice(chunk))).collect::<Vec<_>>();
+        let response = Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from_stream(stream::iter(chunks)))
+            .unwrap();
+        let (response, message) = error_message(response).await;
+        assert_eq!(message.map(|message| message.chars().count()), Some(MAX_TEXT));
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body_of(response).await, body);
+    }
+
+    #[test]
+    fn params_redact_sensitive_fields() {
+        let body = Bytes::from(r#"{"account":"admin","oldPassword":"a","nested":[{"refreshToken":"b"}]}"#);
+        let logged = params(None, &body).unwrap();
+        assert!(logged.contains(r#""account":"admin""#));
+        assert!(!logged.contains(r#""a""#) && !logged.contains(r#""b""#));
+        assert_eq!(logged.matches(REDACTED).count(), 2);
+        assert_eq!(params(Some("page=1"), &Bytes::new()).as_deref(), Some("page=1"));
+    }
+
+    #[test]
+    fn params_redact_sensitive_query() {
+        let logged = params(Some("page=1&token=abc&Reset%5FToken=def&accessToken&name=a%3Db"), &Bytes::new()).unwrap();
+        assert_eq!(logged, "page=1&token=******&Reset%5FToken=******&accessToken&name=a%3Db");
+    }
+}
@@ -8,11 +8,12 @@ pub mod serde;
 pub mod utils;
 pub mod server;
 pub mod middleware;
+pub mod mail;
 
 use sea_orm::DatabaseConnection;
 
 use crate::config;
-use crate::framework::{db::database, middleware::logger, server::Server, utils::generator};
+use crate::framework::{auth::{login_log, permission, provider, revocation}, db::database, middleware::{logger, oper_log}, server::Server, utils::generator};
 
 
 #[derive(Clone)]
@@ -30,9 +31,16 @@ impl AppState {
 pub async fn run(router: axum::Router<AppState>) -> anyhow::Result<()> {
     logger::init();
     generator::init()?;
+    auth::init()?;
+    provider::init()?;
+    mail::init()?;
     tracing::info!("Starting app server...");
 
     let db = database::init().await?;
+    revocation::init(db.clone())?;
+    permission::init(db.clone())?;
+    login_log::init(db.clone());
+    oper_log::init(db.clone())?;
     let state = AppState::new(db);
     let server = Server::new(config::get().server());
 
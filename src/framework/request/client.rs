@@ -0,0 +1,156 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::{header, request::Parts, HeaderMap};
+use woothee::{parser::Parser, woothee::VALUE_UNKNOWN};
+
+use crate::config::{self, server::ServerConfig};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+// 请求方的地址和客户端信息, 用于登录记录、会话等
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub ip: IpAddr,
+    pub user_agent: Option<String>,
+}
+
+// 从 user agent 中解析出的浏览器和操作系统
+#[derive(Debug, Clone, Default)]
+pub struct UserAgentInfo {
+    pub browser: Option<String>,
+    pub os: Option<String>,
+}
+
+impl ClientInfo {
+
+    pub fn parse_user_agent(&self) -> UserAgentInfo {
+        let Some(result) = self.user_agent.as_deref().and_then(|user_agent| Parser::new().parse(user_agent)) else {
+            return UserAgentInfo::default();
+        };
+        UserAgentInfo {
+            browser: known(result.name).map(|name| match known(result.version) {
+                Some(version) => format!("{} {}", name, version),
+                None => name.to_string(),
+            }),
+            os: known(result.os).map(|os| match known(&result.os_version) {
+                // 名称中已经带版本号(如 Windows 10)时不再追加
+                Some(version) if !os.chars().any(|c| c.is_ascii_digit()) => format!("{} {}", os, version),
+                _ => os.to_string(),
+            }),
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // 没有使用 into_make_service_with_connect_info 启动时拿不到对端地址
+        let peer = parts.extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let user_agent = parts.headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+
+        Ok(Self { ip: client_ip(peer, &parts.headers, config::get().server()), user_agent })
+    }
+}
+
+/*
+* 对端是可信代理时才读取 X-Forwarded-For
+*
+* 从右往左依次跳过可信代理, 第一个不可信的地址就是客户端; 左边的部分可以被客户端伪造, 不能直接取第一个
+*/
+fn client_ip(peer: IpAddr, headers: &HeaderMap, server: &ServerConfig) -> IpAddr {
+    if !server.is_trusted_proxy(peer) {
+        return peer;
+    }
+
+    let hops: Vec<&str> = headers
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    let mut client = peer;
+    for hop in hops.into_iter().rev() {
+        match hop.parse::<IpAddr>() {
+            Ok(ip) => {
+                client = ip;
+                if !server.is_trusted_proxy(ip) {
+                    break;
+                }
+            }
+            // 格式不正确时停在最后一个可信的地址
+            Err(_) => break,
+        }
+    }
+    client
+}
+
+fn known(value: &str) -> Option<&str> {
+    Some(value).filter(|value| !value.is_empty() && *value != VALUE_UNKNOWN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(trusted_proxies: &[&str]) -> ServerConfig {
+        serde_json::from_value(serde_json::json!({ "trusted_proxies": trusted_proxies })).unwrap()
+    }
+
+    fn forwarded(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(X_FORWARDED_FOR, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn untrusted_peer_ignores_header() {
+        let headers = forwarded(&["1.1.1.1"]);
+        assert_eq!(client_ip(ip("2.2.2.2"), &headers, &server(&[])), ip("2.2.2.2"));
+        assert_eq!(client_ip(ip("2.2.2.2"), &headers, &server(&["10.0.0.0/8"])), ip("2.2.2.2"));
+    }
+
+    #[test]
+    fn walks_from_the_right_past_trusted_proxies() {
+        let server = server(&["10.0.0.0/8", "127.0.0.1"]);
+        // 最左边的地址由客户端伪造
+        let headers = forwarded(&["6.6.6.6, 1.1.1.1, 10.0.0.2"]);
+        assert_eq!(client_ip(ip("127.0.0.1"), &headers, &server), ip("1.1.1.1"));
+
+        // 多个请求头按顺序拼接
+        let headers = forwarded(&["6.6.6.6", "1.1.1.1", "10.0.0.2"]);
+        assert_eq!(client_ip(ip("127.0.0.1"), &headers, &server), ip("1.1.1.1"));
+    }
+
+    #[test]
+    fn stops_at_last_trusted_hop() {
+        let server = server(&["10.0.0.0/8"]);
+        // 全部是可信代理时取最左边的
+        let headers = forwarded(&["10.0.0.3, 10.0.0.2"]);
+        assert_eq!(client_ip(ip("10.0.0.1"), &headers, &server), ip("10.0.0.3"));
+
+        // 格式不正确时停在最后一个可信的地址
+        let headers = forwarded(&["1.1.1.1, unknown, 10.0.0.2"]);
+        assert_eq!(client_ip(ip("10.0.0.1"), &headers, &server), ip("10.0.0.2"));
+
+        assert_eq!(client_ip(ip("10.0.0.1"), &HeaderMap::new(), &server), ip("10.0.0.1"));
+    }
+}
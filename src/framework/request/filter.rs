@@ -0,0 +1,358 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use sea_orm::sea_query::{LikeExpr, SimpleExpr};
+use sea_orm::{ActiveEnum, ColumnTrait, ColumnType, Condition, Value};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+
+// 多个值之间的分隔符, 如 gender[in]=male,female
+const SEPARATOR: char = ',';
+// like 的转义字符, 参数中的 % 和 _ 按普通字符匹配
+const LIKE_ESCAPE: char = '\\';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    // 包含, 参数中的 % 和 _ 不作为通配符
+    Like,
+    // 逗号分隔的多个值
+    In,
+    // true 为空, false 不为空
+    Null,
+}
+
+impl Operator {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "eq" => Some(Self::Eq),
+            "ne" => Some(Self::Ne),
+            "gt" => Some(Self::Gt),
+            "gte" => Some(Self::Gte),
+            "lt" => Some(Self::Lt),
+            "lte" => Some(Self::Lte),
+            "like" => Some(Self::Like),
+            "in" => Some(Self::In),
+            "null" => Some(Self::Null),
+            _ => None,
+        }
+    }
+}
+
+// 常用的操作符组合
+pub const TEXT: &[Operator] = &[Operator::Eq, Operator::Ne, Operator::Like, Operator::In];
+pub const ENUM: &[Operator] = &[Operator::Eq, Operator::Ne, Operator::In];
+pub const RANGE: &[Operator] = &[Operator::Eq, Operator::Gt, Operator::Gte, Operator::Lt, Operator::Lte];
+pub const BOOL: &[Operator] = &[Operator::Eq];
+
+// 允许过滤的字段: 参数名、对应的列和允许的操作符
+pub struct FilterField<C> {
+    pub name: &'static str,
+    pub column: C,
+    pub operators: &'static [Operator],
+    // 枚举列的可选值, 其他值返回 400
+    pub values: Option<fn() -> Vec<String>>,
+}
+
+impl<C> FilterField<C> {
+    pub const fn new(name: &'static str, column: C, operators: &'static [Operator]) -> Self {
+        Self { name, column, operators, values: None }
+    }
+
+    // 枚举列, 如 FilterField::new("dataScope", sys_role::Column::DataScope, filter::ENUM).enumeration::<DataScope>()
+    pub const fn enumeration<E: ActiveEnum<Value = String>>(mut self) -> Self {
+        self.values = Some(E::values);
+        self
+    }
+}
+
+/*
+* 实体的过滤字段白名单
+*
+* pub struct RoleFilterFields;
+*
+* impl FilterFields for RoleFilterFields {
+*     type Column = sys_role::Column;
+*     const FIELDS: &'static [FilterField<Self::Column>] = &[
+*         FilterField::new("name", sys_role::Column::Name, filter::TEXT),
+*         FilterField::new("createdAt", sys_role::Column::CreatedAt, filter::RANGE),
+*     ];
+* }
+*/
+pub trait FilterFields {
+    type Column: ColumnTrait;
+    const FIELDS: &'static [FilterField<Self::Column>];
+}
+
+/*
+* 从 query 参数中解析过滤条件, 如 name[like]=ab&createdAt[gte]=2025-01-01&gender[in]=male,female
+*
+* 不带操作符时按 eq 处理; 不在白名单中的字段或操作符直接反序列化失败, 由 Query 抽取器转换为 400;
+* 与其他参数一起使用时通过 flatten 放在最后, 前面的字段会先取走自己的参数:
+*
+* #[serde(flatten)]
+* pagination: PaginationParams,
+* #[serde(flatten)]
+* filter: Filter<RoleFilterFields>,
+*/
+pub struct Filter<T: FilterFields> {
+    condition: Condition,
+    _fields: PhantomData<T>,
+}
+
+impl<T: FilterFields> Filter<T> {
+    pub fn condition(&self) -> Condition {
+        self.condition.clone()
+    }
+
+    fn add(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let (name, operator) = match key.split_once('[') {
+            Some((name, operator)) => {
+                let operator = operator
+                    .strip_suffix(']')
+                    .and_then(Operator::parse)
+                    .ok_or_else(|| format!("不支持的查询条件: {}", key))?;
+                (name, operator)
+            }
+            None => (key, Operator::Eq),
+        };
+        let field = T::FIELDS
+            .iter()
+            .find(|field| field.name == name)
+            .ok_or_else(|| format!("不支持的查询字段: {}", name))?;
+        if !field.operators.contains(&operator) {
+            return Err(format!("不支持的查询条件: {}", key));
+        }
+
+        let expr = expression(field, operator, value).map_err(|e| format!("{}: {}", key, e))?;
+        self.condition = std::mem::replace(&mut self.condition, Condition::all()).add(expr);
+        Ok(())
+    }
+}
+
+impl<T: FilterFields> Default for Filter<T> {
+    fn default() -> Self {
+        Self { condition: Condition::all(), _fields: PhantomData }
+    }
+}
+
+impl<T: FilterFields> fmt::Debug for Filter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filter").field("condition", &self.condition).finish()
+    }
+}
+
+impl<'de, T: FilterFields> Deserialize<'de> for Filter<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(FilterVisitor(PhantomData))
+    }
+}
+
+struct FilterVisitor<T>(PhantomData<T>);
+
+impl<'de, T: FilterFields> Visitor<'de> for FilterVisitor<T> {
+    type Value = Filter<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("query filters")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut filter = Filter::default();
+        while let Some((key, value)) = map.next_entry::<String, String>()? {
+            filter.add(&key, &value).map_err(de::Error::custom)?;
+        }
+        Ok(filter)
+    }
+}
+
+// 包含 value, 转义其中的通配符
+pub fn contains<C: ColumnTrait>(column: C, value: &str) -> SimpleExpr {
+    let mut pattern = String::with_capacity(value.len() + 2);
+    pattern.push('%');
+    for c in value.chars() {
+        if matches!(c, '%' | '_') || c == LIKE_ESCAPE {
+            pattern.push(LIKE_ESCAPE);
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    column.like(LikeExpr::new(pattern).escape(LIKE_ESCAPE))
+}
+
+fn expression<C: ColumnTrait>(field: &FilterField<C>, operator: Operator, value: &str) -> Result<SimpleExpr, String> {
+    let column = field.column;
+    let column_type = column.def().get_column_type().clone();
+    let parse = |value: &str| match field.values {
+        Some(values) => parse_enum(&values(), value),
+        None => parse_value(&column_type, value),
+    };
+    match operator {
+        Operator::Null => match value {
+            "true" => Ok(column.is_null()),
+            "false" => Ok(column.is_not_null()),
+            _ => Err(String::from("只能是 true 或 false")),
+        },
+        Operator::Like => Ok(contains(column, value)),
+        Operator::In => {
+            let values = value
+                .split(SEPARATOR)
+                .map(|value| parse(value.trim()))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(column.is_in(values))
+        }
+        _ => {
+            // 时间列只传日期时按整天处理, 如 createdAt[lte]=2025-01-31 包含 31 号当天
+            if is_date_time(&column_type) && let Ok(date) = value.parse::<NaiveDate>() {
+                let begin = date.and_time(NaiveTime::MIN);
+                let end = begin + chrono::Duration::days(1);
+                return Ok(match operator {
+                    Operator::Eq => column.gte(begin).and(column.lt(end)),
+                    Operator::Ne => column.lt(begin).or(column.gte(end)),
+                    Operator::Gt => column.gte(end),
+                    Operator::Gte => column.gte(begin),
+                    Operator::Lt => column.lt(begin),
+                    _ => column.lt(end),
+                });
+            }
+            let value = parse(value)?;
+            Ok(match operator {
+                Operator::Eq => column.eq(value),
+                Operator::Ne => column.ne(value),
+                Operator::Gt => column.gt(value),
+                Operator::Gte => column.gte(value),
+                Operator::Lt => column.lt(value),
+                _ => column.lte(value),
+            })
+        }
+    }
+}
+
+fn is_date_time(column_type: &ColumnType) -> bool {
+    matches!(column_type, ColumnType::DateTime | ColumnType::Timestamp | ColumnType::TimestampWithTimeZone)
+}
+
+fn parse_enum(values: &[String], value: &str) -> Result<Value, String> {
+    match values.iter().any(|v| v == value) {
+        true => Ok(Value::from(value)),
+        false => Err(format!("{} 不是有效的值, 可选值: {}", value, values.join(", "))),
+    }
+}
+
+// 按列的类型转换参数, 其余类型按字符串比较
+fn parse_value(column_type: &ColumnType, value: &str) -> Result<Value, String> {
+    let invalid = |expected: &str| format!("{} 不是有效的{}", value, expected);
+    match column_type {
+        ColumnType::Boolean => value.parse::<bool>().map(Value::from).map_err(|_| invalid("布尔值")),
+        ColumnType::TinyInteger | ColumnType::SmallInteger | ColumnType::Integer | ColumnType::BigInteger => {
+            value.parse::<i64>().map(Value::from).map_err(|_| invalid("整数"))
+        }
+        ColumnType::TinyUnsigned | ColumnType::SmallUnsigned | ColumnType::Unsigned | ColumnType::BigUnsigned => {
+            value.parse::<u64>().map(Value::from).map_err(|_| invalid("整数"))
+        }
+        ColumnType::Float | ColumnType::Double | ColumnType::Decimal(_) => {
+            value.parse::<f64>().map(Value::from).map_err(|_| invalid("数字"))
+        }
+        ColumnType::Date => value.parse::<NaiveDate>().map(Value::from).map_err(|_| invalid("日期")),
+        column_type if is_date_time(column_type) => {
+            value.parse::<NaiveDateTime>().map(Value::from).map_err(|_| invalid("时间"))
+        }
+        _ => Ok(Value::from(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::sea_query::{PostgresQueryBuilder, Query};
+
+    use super::*;
+    use crate::entity::sys_role;
+    use crate::enums::DataScope;
+
+    struct RoleFields;
+
+    impl FilterFields for RoleFields {
+        type Column = sys_role::Column;
+        const FIELDS: &'static [FilterField<Self::Column>] = &[
+            FilterField::new("code", sys_role::Column::Code, TEXT),
+            FilterField::new("description", sys_role::Column::Description, &[Operator::Null]),
+            FilterField::new("enabled", sys_role::Column::Enabled, BOOL),
+            FilterField::new("dataScope", sys_role::Column::DataScope, ENUM).enumeration::<DataScope>(),
+            FilterField::new("createdAt", sys_role::Column::CreatedAt, RANGE),
+        ];
+    }
+
+    fn sql(params: &[(&str, &str)]) -> Result<String, String> {
+        let mut filter = Filter::<RoleFields>::default();
+        for (key, value) in params {
+            filter.add(key, value)?;
+        }
+        Ok(Query::select().cond_where(filter.condition()).to_string(PostgresQueryBuilder))
+    }
+
+    #[test]
+    fn add_parses_operators() {
+        assert!(sql(&[("code", "admin")]).unwrap().contains(r#""code" = 'admin'"#));
+        assert!(sql(&[("code[ne]", "admin")]).unwrap().contains(r#""code" <> 'admin'"#));
+        assert!(sql(&[("code[in]", "a, b")]).unwrap().contains(r#""code" IN ('a', 'b')"#));
+        assert!(sql(&[("enabled", "true")]).unwrap().contains(r#""enabled" = TRUE"#));
+        assert!(sql(&[("description[null]", "false")]).unwrap().contains(r#""description" IS NOT NULL"#));
+    }
+
+    #[test]
+    fn add_rejects_unknown_fields_and_operators() {
+        assert!(sql(&[("name", "admin")]).unwrap_err().contains("不支持的查询字段"));
+        assert!(sql(&[("code[gt]", "a")]).is_err());
+        assert!(sql(&[("code[foo]", "a")]).is_err());
+        assert!(sql(&[("code[like", "a")]).is_err());
+        assert!(sql(&[("enabled", "yes")]).is_err());
+        assert!(sql(&[("code[null]", "true")]).is_err());
+        assert!(sql(&[("description[null]", "1")]).is_err());
+    }
+
+    #[test]
+    fn date_on_date_time_column_covers_whole_day() {
+        let lte = sql(&[("createdAt[lte]", "2025-01-31")]).unwrap();
+        assert!(lte.contains(r#""created_at" < '2025-02-01 00:00:00'"#), "{}", lte);
+
+        let eq = sql(&[("createdAt", "2025-01-31")]).unwrap();
+        assert!(eq.contains(r#""created_at" >= '2025-01-31 00:00:00'"#), "{}", eq);
+        assert!(eq.contains(r#""created_at" < '2025-02-01 00:00:00'"#), "{}", eq);
+
+        let gt = sql(&[("createdAt[gt]", "2025-01-31")]).unwrap();
+        assert!(gt.contains(r#""created_at" >= '2025-02-01 00:00:00'"#), "{}", gt);
+
+        let exact = sql(&[("createdAt[gte]", "2025-01-31T08:30:00")]).unwrap();
+        assert!(exact.contains(r#""created_at" >= '2025-01-31 08:30:00'"#), "{}", exact);
+        assert!(sql(&[("createdAt[gte]", "yesterday")]).is_err());
+    }
+
+    #[test]
+    fn like_escapes_wildcards() {
+        let like = sql(&[("code[like]", "a%_b")]).unwrap();
+        assert!(like.contains(r#"LIKE E'%a\\%\\_b%' ESCAPE E'\\'"#), "{}", like);
+    }
+
+    #[test]
+    fn enum_values_are_validated() {
+        assert!(sql(&[("dataScope", "dept_and_children")]).unwrap().contains(r#""data_scope" = 'dept_and_children'"#));
+        assert!(sql(&[("dataScope", "bogus")]).unwrap_err().contains("不是有效的值"));
+        assert!(sql(&[("dataScope[in]", "all,bogus")]).is_err());
+    }
+
+    #[test]
+    fn deserialize_collects_all_conditions() {
+        let value = serde_json::json!({ "code[like]": "adm", "enabled": "true" });
+        let filter: Filter<RoleFields> = serde_json::from_value(value).unwrap();
+        let sql = Query::select().cond_where(filter.condition()).to_string(PostgresQueryBuilder);
+        assert!(sql.contains("LIKE") && sql.contains(r#""enabled" = TRUE"#), "{}", sql);
+
+        let value = serde_json::json!({ "unknown": "1" });
+        assert!(serde_json::from_value::<Filter<RoleFields>>(value).is_err());
+    }
+}
@@ -1,2 +1,4 @@
+pub mod client;
+pub mod filter;
 pub mod valid;
 pub mod param_valid;
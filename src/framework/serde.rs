@@ -21,3 +21,85 @@ where
         StringOrNumber::Number(n) => Ok(n),
     }
 }
+
+// 可选的 query 参数, flatten 之后所有值都按字符串传入, 空字符串视为未传
+//
+// #[serde(default, deserialize_with = "deserialize_optional")]
+pub fn deserialize_optional<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: FromStr + Deserialize<'de>,
+    T::Err: Display,
+    D: Deserializer<'de>
+{
+    match Option::<StringOrNumber<T>>::deserialize(deserializer)? {
+        Some(StringOrNumber::String(s)) if s.is_empty() => Ok(None),
+        Some(StringOrNumber::String(s)) => s.parse().map(Some).map_err(Error::custom),
+        Some(StringOrNumber::Number(n)) => Ok(Some(n)),
+        None => Ok(None),
+    }
+}
+
+// 部分更新中可以置空的字段: 未传时为 None, 传 null 时为 Some(None)
+//
+// #[serde(default, deserialize_with = "deserialize_nullable")]
+pub fn deserialize_nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Query {
+        #[serde(default, deserialize_with = "deserialize_optional")]
+        age: Option<u32>,
+        #[serde(default, deserialize_with = "deserialize_optional")]
+        enabled: Option<bool>,
+    }
+
+    fn query(json: &str) -> Result<Query, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn optional_parses_strings_and_numbers() {
+        let query = query(r#"{"age":"18","enabled":"true"}"#).unwrap();
+        assert_eq!((query.age, query.enabled), (Some(18), Some(true)));
+        assert_eq!(self::query(r#"{"age":18}"#).unwrap().age, Some(18));
+    }
+
+    #[test]
+    fn optional_treats_missing_and_empty_as_none() {
+        let query = query(r#"{"age":""}"#).unwrap();
+        assert_eq!((query.age, query.enabled), (None, None));
+        assert_eq!(self::query(r#"{"age":null}"#).unwrap().age, None);
+    }
+
+    #[test]
+    fn optional_rejects_invalid_values() {
+        assert!(query(r#"{"age":"abc"}"#).is_err());
+        assert!(query(r#"{"enabled":"yes"}"#).is_err());
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Patch {
+        #[serde(default, deserialize_with = "deserialize_nullable")]
+        email: Option<Option<String>>,
+    }
+
+    #[test]
+    fn nullable_distinguishes_missing_null_and_value() {
+        let patch = |json: &str| serde_json::from_str::<Patch>(json).unwrap().email;
+        assert_eq!(patch("{}"), None);
+        assert_eq!(patch(r#"{"email":null}"#), Some(None));
+        assert_eq!(patch(r#"{"email":"a@b.com"}"#), Some(Some(String::from("a@b.com"))));
+        assert!(serde_json::from_str::<Patch>(r#"{"email":1}"#).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgb, RgbImage};
+use rand::Rng;
+
+// 5x7 点阵字体, 每行低 5 位从左到右
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+// 每个点放大的像素数
+const SCALE: u32 = 3;
+// 字符之间和左右两侧的间距
+const GAP: u32 = 6;
+const PADDING: u32 = 10;
+const HEIGHT: u32 = 40;
+const NOISE_LINES: usize = 4;
+const NOISE_DOTS: usize = 120;
+
+const GLYPHS: &[(char, [u8; 7])] = &[
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('×', [0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b00000]),
+    ('=', [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]),
+];
+
+// 容易混淆的 0/O、1/I 不参与随机字符
+pub const CHARSET: &[u8] = b"23456789ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+fn glyph(c: char) -> Option<&'static [u8; 7]> {
+    GLYPHS.iter().find(|(glyph, _)| *glyph == c).map(|(_, rows)| rows)
+}
+
+/*
+* 把验证码文本绘制为 PNG
+*
+* 每个字符随机颜色、上下偏移和倾斜, 再叠加干扰线和噪点; 字体中没有的字符留空
+*/
+pub fn render(text: &str) -> anyhow::Result<Vec<u8>> {
+    let mut rng = rand::thread_rng();
+    let cell = GLYPH_WIDTH * SCALE + GAP;
+    let width = text.chars().count() as u32 * cell - GAP + PADDING * 2;
+    let glyph_height = GLYPH_HEIGHT * SCALE;
+
+    let background = Rgb([rng.gen_range(235..=255), rng.gen_range(235..=255), rng.gen_range(235..=255)]);
+    let mut image = RgbImage::from_pixel(width, HEIGHT, background);
+
+    for (i, c) in text.chars().enumerate() {
+        let Some(rows) = glyph(c) else {
+            continue;
+        };
+        let color = Rgb([rng.gen_range(20..120), rng.gen_range(20..120), rng.gen_range(20..120)]);
+        let left = (PADDING + i as u32 * cell) as i32;
+        let top = rng.gen_range(3..=HEIGHT - glyph_height - 3) as i32;
+        // 以字符中线为轴水平错切
+        let shear: f32 = rng.gen_range(-0.35..0.35);
+
+        for (row, bits) in rows.iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) == 0 {
+                    continue;
+                }
+                for dy in 0..SCALE {
+                    let y = row as u32 * SCALE + dy;
+                    let offset = (shear * (glyph_height as f32 / 2.0 - y as f32)).round() as i32;
+                    for dx in 0..SCALE {
+                        let x = left + (col * SCALE + dx) as i32 + offset;
+                        put_pixel(&mut image, x, top + y as i32, color);
+                    }
+                }
+            }
+        }
+    }
+
+    for _ in 0..NOISE_LINES {
+        let color = Rgb([rng.gen_range(80..200), rng.gen_range(80..200), rng.gen_range(80..200)]);
+        let from = (rng.gen_range(0..width / 2) as i32, rng.gen_range(0..HEIGHT) as i32);
+        let to = (rng.gen_range(width / 2..width) as i32, rng.gen_range(0..HEIGHT) as i32);
+        draw_line(&mut image, from, to, color);
+    }
+    for _ in 0..NOISE_DOTS {
+        let color = Rgb([rng.gen_range(0..=255), rng.gen_range(0..=255), rng.gen_range(0..=255)]);
+        let (x, y) = (rng.gen_range(0..width), rng.gen_range(0..HEIGHT));
+        image.put_pixel(x, y, color);
+    }
+
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+fn put_pixel(image: &mut RgbImage, x: i32, y: i32, color: Rgb<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+fn draw_line(image: &mut RgbImage, (x0, y0): (i32, i32), (x1, y1): (i32, i32), color: Rgb<u8>) {
+    let steps = (x1 - x0).abs().max((y1 - y0).abs()).max(1);
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let x = x0 as f32 + (x1 - x0) as f32 * t;
+        let y = y0 as f32 + (y1 - y0) as f32 * t;
+        put_pixel(image, x.round() as i32, y.round() as i32, color);
+    }
+}
@@ -1,2 +1,5 @@
 pub mod validation;
 pub mod generator;
+pub mod crypto;
+pub mod tree;
+pub mod captcha_image;
@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+
+// 可以组装成树的数据
+pub trait TreeItem {
+    fn id(&self) -> &str;
+    fn parent_id(&self) -> Option<&str>;
+}
+
+#[derive(Debug, Serialize)]
+pub struct TreeNode<T> {
+    #[serde(flatten)]
+    pub node: T,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreeNode<T>>,
+}
+
+// 按 parent_id 组装成树, 同级保持传入的顺序
+// 父节点不在列表中的节点作为根节点
+pub fn build_tree<T: TreeItem>(items: Vec<T>) -> Vec<TreeNode<T>> {
+    let ids: HashSet<String> = items.iter().map(|item| item.id().to_string()).collect();
+
+    let mut roots = Vec::new();
+    let mut children: HashMap<String, Vec<T>> = HashMap::new();
+    for item in items {
+        match item.parent_id().filter(|parent_id| ids.contains(*parent_id)) {
+            Some(parent_id) => children.entry(parent_id.to_string()).or_default().push(item),
+            None => roots.push(item),
+        }
+    }
+
+    attach(roots, &mut children)
+}
+
+fn attach<T: TreeItem>(items: Vec<T>, children: &mut HashMap<String, Vec<T>>) -> Vec<TreeNode<T>> {
+    items
+        .into_iter()
+        .map(|item| {
+            let sub = children.remove(item.id()).unwrap_or_default();
+            TreeNode {
+                children: attach(sub, children),
+                node: item,
+            }
+        })
+        .collect()
+}
+
+// 指定节点的所有下级节点 id, 不包含自身
+pub fn descendant_ids<T: TreeItem>(items: &[T], id: &str) -> HashSet<String> {
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    for item in items {
+        if let Some(parent_id) = item.parent_id() {
+            children.entry(parent_id).or_default().push(item.id());
+        }
+    }
+
+    let mut result = HashSet::new();
+    let mut stack = vec![id];
+    while let Some(current) = stack.pop() {
+        for child in children.get(current).into_iter().flatten() {
+            // 防御脏数据中的环
+            if result.insert(child.to_string()) {
+                stack.push(child);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Item(&'static str, Option<&'static str>);
+
+    impl TreeItem for Item {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        fn parent_id(&self) -> Option<&str> {
+            self.1
+        }
+    }
+
+    fn ids<T: TreeItem>(nodes: &[TreeNode<T>]) -> Vec<&str> {
+        nodes.iter().map(|node| node.node.id()).collect()
+    }
+
+    #[test]
+    fn build_tree_keeps_order() {
+        let items = vec![Item("1", None), Item("2", Some("1")), Item("3", None), Item("4", Some("1")), Item("5", Some("4"))];
+        let tree = build_tree(items);
+        assert_eq!(ids(&tree), ["1", "3"]);
+        assert_eq!(ids(&tree[0].children), ["2", "4"]);
+        assert_eq!(ids(&tree[0].children[1].children), ["5"]);
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn build_tree_promotes_orphans_to_roots() {
+        // 父节点被过滤掉时, 子节点作为根节点
+        let tree = build_tree(vec![Item("2", Some("1")), Item("3", Some("2"))]);
+        assert_eq!(ids(&tree), ["2"]);
+        assert_eq!(ids(&tree[0].children), ["3"]);
+    }
+
+    #[test]
+    fn descendant_ids_excludes_self() {
+        let items = [Item("1", None), Item("2", Some("1")), Item("3", Some("2")), Item("4", None), Item("5", Some("4"))];
+        let ids = descendant_ids(&items, "1");
+        assert_eq!(ids, HashSet::from([String::from("2"), String::from("3")]));
+        assert!(descendant_ids(&items, "3").is_empty());
+        assert!(descendant_ids(&items, "unknown").is_empty());
+    }
+
+    #[test]
+    fn descendant_ids_stops_on_cycles() {
+        let items = [Item("1", Some("3")), Item("2", Some("1")), Item("3", Some("2"))];
+        let ids = descendant_ids(&items, "1");
+        assert_eq!(ids.len(), 3);
+    }
+}
@@ -1,12 +1,33 @@
 
-use std::{borrow::Cow, cell::LazyCell, collections::HashMap};
+use std::{borrow::Cow, collections::{HashMap, HashSet}, sync::LazyLock};
 
 use regex::Regex;
 use validator::ValidationError;
 
+use crate::config::{self, auth::PasswordPolicyConfig};
 
-const MOBILE_PHONE_REGEX: LazyCell<Regex> = 
-    LazyCell::new(|| Regex::new(r"^1[3-9]\d{9}$").expect("Fail compile mobile phone regex"));
+
+static MOBILE_PHONE_REGEX: LazyLock<Regex> = 
+    LazyLock::new(|| Regex::new(r"^1[3-9]\d{9}$").expect("Fail compile mobile phone regex"));
+
+// 权限标识, 冒号分隔的小写单词, 如 system:user:delete
+static PERMISSION_CODE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z][a-z0-9_-]*(:[a-z][a-z0-9_-]*)*$").expect("Fail compile permission code regex"));
+
+// 禁止使用的密码, 来自配置中的 banned 和 banned_file
+static BANNED_PASSWORDS: LazyLock<HashSet<String>> = LazyLock::new(|| {
+    let policy = config::get().auth().password();
+    let mut banned: HashSet<String> = policy.banned().iter().map(|password| password.to_lowercase()).collect();
+    if let Some(path) = policy.banned_file() {
+        match std::fs::read_to_string(path) {
+            Ok(content) => banned.extend(
+                content.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_lowercase)
+            ),
+            Err(e) => tracing::warn!("Fail to read banned password file {}: {}", path, e),
+        }
+    }
+    banned
+});
 
 
 pub fn is_mobile_phone(value: &str) -> Result<(), ValidationError> {
@@ -17,10 +38,124 @@ pub fn is_mobile_phone(value: &str) -> Result<(), ValidationError> {
     }
 }
 
-fn build_validation_error(message: &'static str) -> ValidationError {
+pub fn is_permission_code(value: &str) -> Result<(), ValidationError> {
+    if value.len() <= 128 && PERMISSION_CODE_REGEX.is_match(value) {
+        Ok(())
+    } else {
+        Err(build_validation_error("权限标识格式不正确, 如 system:user:delete"))
+    }
+}
+
+// 按配置的密码策略校验: 长度、字符种类、常见密码
+pub fn is_strong_password(value: &str) -> Result<(), ValidationError> {
+    check_strength(value, config::get().auth().password(), &BANNED_PASSWORDS)
+}
+
+fn check_strength(value: &str, policy: &PasswordPolicyConfig, banned: &HashSet<String>) -> Result<(), ValidationError> {
+    let length = value.chars().count();
+    if length < policy.min_length() || length > policy.max_length() {
+        return Err(build_validation_error(format!(
+            "密码长度{}-{}", policy.min_length(), policy.max_length()
+        )));
+    }
+
+    let classes = [
+        (policy.require_lowercase(), value.chars().any(|c| c.is_ascii_lowercase()), "小写字母"),
+        (policy.require_uppercase(), value.chars().any(|c| c.is_ascii_uppercase()), "大写字母"),
+        (policy.require_digit(), value.chars().any(|c| c.is_ascii_digit()), "数字"),
+        (policy.require_symbol(), value.chars().any(|c| !c.is_ascii_alphanumeric()), "特殊字符"),
+    ];
+    let missing: Vec<&str> = classes
+        .iter()
+        .filter(|(required, present, _)| *required && !present)
+        .map(|(_, _, name)| *name)
+        .collect();
+    if !missing.is_empty() {
+        return Err(build_validation_error(format!("密码必须包含{}", missing.join("、"))));
+    }
+
+    if banned.contains(&value.to_lowercase()) {
+        return Err(build_validation_error("密码过于常见, 请更换"));
+    }
+    Ok(())
+}
+
+// 在密码策略基础上, 密码不能与账号、手机号等个人信息相同
+pub fn check_password(value: &str, personal: &[&str]) -> Result<(), ValidationError> {
+    is_strong_password(value)?;
+    if personal.iter().any(|info| !info.is_empty() && info.eq_ignore_ascii_case(value)) {
+        return Err(build_validation_error("密码不能与账号或手机号相同"));
+    }
+    Ok(())
+}
+
+// 范围查询的开始不能晚于结束, 只传一端时不校验
+pub fn check_range<T: PartialOrd>(begin: Option<&T>, end: Option<&T>, message: &'static str) -> Result<(), ValidationError> {
+    match begin.zip(end) {
+        Some((begin, end)) if begin > end => Err(build_validation_error(message)),
+        _ => Ok(()),
+    }
+}
+
+fn build_validation_error(message: impl Into<Cow<'static, str>>) -> ValidationError {
     ValidationError {
         code: Cow::from("invalid"),
-        message: Some(Cow::from(message)),
+        message: Some(message.into()),
         params: HashMap::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(value: serde_json::Value) -> PasswordPolicyConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn message(result: Result<(), ValidationError>) -> String {
+        result.unwrap_err().message.unwrap().to_string()
+    }
+
+    #[test]
+    fn strength_checks_length() {
+        let policy = policy(serde_json::json!({ "min_length": 8, "max_length": 12 }));
+        let banned = HashSet::new();
+        assert_eq!(message(check_strength("abc123", &policy, &banned)), "密码长度8-12");
+        assert_eq!(message(check_strength("abcdefg1234567", &policy, &banned)), "密码长度8-12");
+        assert!(check_strength("abcd1234", &policy, &banned).is_ok());
+        // 按字符而不是字节计算长度
+        assert!(check_strength("密码密码密码ab1", &policy, &banned).is_ok());
+    }
+
+    #[test]
+    fn strength_checks_character_classes() {
+        let policy = policy(serde_json::json!({ "require_uppercase": true, "require_symbol": true }));
+        let banned = HashSet::new();
+        assert_eq!(message(check_strength("abcd1234", &policy, &banned)), "密码必须包含大写字母、特殊字符");
+        assert_eq!(message(check_strength("ABCD!234", &policy, &banned)), "密码必须包含小写字母");
+        assert!(check_strength("Abcd!234", &policy, &banned).is_ok());
+    }
+
+    #[test]
+    fn strength_rejects_banned_ignoring_case() {
+        let policy = policy(serde_json::json!({}));
+        let banned = HashSet::from([String::from("password1")]);
+        assert_eq!(message(check_strength("PassWord1", &policy, &banned)), "密码过于常见, 请更换");
+    }
+
+    #[test]
+    fn password_differs_from_personal_info() {
+        // 使用 application.yml 中的密码策略
+        assert!(check_password("Zx9pq7wt", &["admin", "13800000000"]).is_ok());
+        assert_eq!(message(check_password("zx9pq7wt", &["ZX9PQ7WT"])), "密码不能与账号或手机号相同");
+        assert!(check_password("Zx9pq7wt", &[""]).is_ok());
+    }
+
+    #[test]
+    fn range_allows_open_ends() {
+        assert!(check_range(Some(&1), Some(&2), "invalid").is_ok());
+        assert!(check_range(Some(&2), None, "invalid").is_ok());
+        assert!(check_range(Some(&2), Some(&1), "invalid").is_err());
+    }
+}
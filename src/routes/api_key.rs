@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use chrono::{Duration, Local};
+use sea_orm::{prelude::*, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entity::{prelude::{SysApiKey, SysPermission}, sys_api_key, sys_permission};
+use crate::framework::AppState;
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::auth::{api_key, extractor::require_interactive, permission::Authorities, Principal};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::request::param_valid::Path;
+use crate::framework::request::valid::ValidJson;
+use crate::framework::response::ApiResponse;
+
+// 当前登录用户管理自己的 API key, 供脚本等机器客户端使用
+// API key 不能再查看、创建或吊销 API key, 需要用户登录后操作
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(query_api_keys))
+        .route("/create", routing::post(create_api_key).route_layer(oper_log("API key", "新增 API key")))
+        .route("/revoke/{id}", routing::post(revoke_api_key).route_layer(oper_log("API key", "吊销 API key")))
+        .route_layer(require_interactive())
+}
+
+#[debug_handler]
+async fn query_api_keys(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+) -> ApiResult<ApiResponse<Vec<sys_api_key::Model>>> {
+    let api_keys = SysApiKey::find()
+        .filter(sys_api_key::Column::UserId.eq(&principal.id))
+        .order_by_desc(sys_api_key::Column::CreatedAt)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(api_keys)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyParams {
+    #[validate(length(min = 1, max = 64, message = "名称长度必须在1到64之间"))]
+    pub name: String,
+    // 权限标识, 必须是当前用户拥有的权限
+    #[validate(length(min = 1, message = "至少需要一个授权范围"))]
+    pub scopes: Vec<String>,
+    // 有效天数, 为空时永不过期
+    #[validate(range(min = 1, max = 3650, message = "有效天数必须在1到3650之间"))]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiKey {
+    // 只返回这一次, 需要用户自行保存
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: sys_api_key::Model,
+}
+
+#[debug_handler]
+async fn create_api_key(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    authorities: Authorities,
+    ValidJson(params): ValidJson<ApiKeyParams>,
+) -> ApiResult<ApiResponse<CreatedApiKey>> {
+    let scopes: Vec<String> = params.scopes
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let existed: Vec<String> = SysPermission::find()
+        .select_only()
+        .column(sys_permission::Column::Code)
+        .filter(sys_permission::Column::Code.is_in(&scopes))
+        .into_tuple()
+        .all(&db)
+        .await?;
+    if let Some(scope) = scopes.iter().find(|scope| !existed.contains(scope)) {
+        return Err(ApiError::Biz(format!("权限不存在: {}", scope)));
+    }
+    if let Some(scope) = scopes.iter().find(|scope| !authorities.has_permission(scope)) {
+        return Err(ApiError::Forbidden(format!("没有权限: {}", scope)));
+    }
+
+    let expires_at = params.expires_in_days
+        .map(|days| Local::now().naive_local() + Duration::days(days as i64));
+    let (key, api_key) = api_key::issue(&db, &principal.id, &params.name, scopes, expires_at).await?;
+    tracing::info!("api key created: {} {}", principal.id, api_key.prefix);
+
+    Ok(ApiResponse::ok("ok", Some(CreatedApiKey { key, api_key })))
+}
+
+#[debug_handler]
+async fn revoke_api_key(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<()>> {
+    if !api_key::revoke(&db, &principal.id, &id).await? {
+        return Err(ApiError::Biz(String::from("API key 不存在或已吊销")));
+    }
+    tracing::info!("api key revoked: {} {}", principal.id, id);
+
+    Ok(ApiResponse::ok("ok", None))
+}
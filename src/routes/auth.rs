@@ -0,0 +1,451 @@
+use axum::{Json, Router, debug_handler, routing};
+
+use axum::extract::State;
+use axum::http::HeaderMap;
+use jsonwebtoken::jwk::JwkSet;
+use chrono::Local;
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entity::{prelude::SysUser, sys_user};
+use crate::framework::AppState;
+use crate::config;
+use crate::framework::auth::{captcha::{self, Captcha}, get_jwt, lockout, login_log, mfa, password, permission::Authorities, provider, refresh, reset, revocation, session, Claims, Principal};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::mail::{self, Mail};
+use crate::framework::request::client::ClientInfo;
+use crate::framework::request::valid::ValidJson;
+use crate::framework::response::ApiResponse;
+use crate::framework::utils::generator::next_id;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/captcha", routing::get(captcha))
+        .route("/login", routing::post(login))
+        .route("/login/mfa", routing::post(login_mfa))
+        .route("/refresh", routing::post(refresh_token))
+        .route("/logout", routing::post(logout))
+        .route("/password", routing::post(change_password))
+        .route("/forgot-password", routing::post(forgot_password))
+        .route("/reset-password", routing::post(reset_password))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct LoginParams {
+    #[validate(length(min = 1, max = 20, message = "账号长度1-20"))]
+    pub account: String,
+
+    #[validate(length(min = 1, message = "密码不能为空"))]
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginResult {
+    pub access_token: String,
+    pub token_type: &'static str,
+    // 有效时长(秒)
+    pub expires_in: u64,
+    pub refresh_token: String,
+    pub refresh_expires_in: u64,
+}
+
+// 开启两步验证的用户, 登录第一步只返回临时 token
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaChallenge {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginStep {
+    Token(LoginResult),
+    Mfa(MfaChallenge),
+}
+
+// 获取图形验证码, 登录等接口通过请求头 X-Captcha-Id / X-Captcha-Code 提交
+#[debug_handler]
+async fn captcha(client: ClientInfo) -> ApiResult<ApiResponse<Captcha>> {
+    lockout::throttle_captcha(client.ip)?;
+    Ok(ApiResponse::ok("ok", Some(captcha::generate()?)))
+}
+
+// 每次登录尝试都记录登录日志
+#[debug_handler]
+async fn login(
+    State(AppState { db }): State<AppState>,
+    client: ClientInfo,
+    headers: HeaderMap,
+    ValidJson(params): ValidJson<LoginParams>,
+) -> ApiResult<ApiResponse<LoginStep>> {
+    // 验证码在校验密码之前检查, 错误时不计入登录失败次数
+    let result = match check_captcha(&headers) {
+        Ok(()) => authenticate(&db, &client, &params).await,
+        Err(e) => Err(e),
+    };
+    let outcome = match &result {
+        Ok(LoginStep::Token(_)) => Ok(login_log::LOGIN_SUCCESS),
+        Ok(LoginStep::Mfa(_)) => Ok(login_log::MFA_REQUIRED),
+        Err(e) => Err(e),
+    };
+    login_log::record(&db, &params.account, &client, outcome).await;
+
+    Ok(ApiResponse::ok("ok", Some(result?)))
+}
+
+// 登录、修改密码和忘记密码都是公开接口, 开启验证码时统一要求提交
+fn check_captcha(headers: &HeaderMap) -> ApiResult<()> {
+    if config::get().auth().captcha().login() {
+        captcha::verify_headers(headers)?;
+    }
+    Ok(())
+}
+
+async fn authenticate(db: &DatabaseConnection, client: &ClientInfo, params: &LoginParams) -> ApiResult<LoginStep> {
+    // 锁定期间不再校验密码
+    lockout::check(&params.account, client.ip)?;
+
+    // 先校验密码再判断是否启用，避免未认证的请求探测账号状态
+    // 账号不存在同样计入失败次数, 避免通过锁定与否探测账号是否存在
+    let user = match provider::authenticate(db, &params.account, &params.password, true).await? {
+        Some(user) => user,
+        None => {
+            lockout::record_failure(&params.account, client.ip)?;
+            return Err(ApiError::Unauthenticated(String::from("账号或密码错误")));
+        }
+    };
+    if !user.enabled {
+        return Err(ApiError::Unauthenticated(String::from("账号已被禁用")));
+    }
+    if password::is_expired(&user) {
+        return Err(ApiError::PasswordExpired);
+    }
+    // 开启两步验证时, 验证码通过后才清除失败次数, 否则可以用密码反复重置验证码的尝试次数
+    if mfa::is_enabled(db, &user.id).await? {
+        let challenge = MfaChallenge {
+            mfa_required: true,
+            mfa_token: mfa::issue_challenge(&user)?,
+            expires_in: config::get().auth().mfa().token_ttl(),
+        };
+        return Ok(LoginStep::Mfa(challenge));
+    }
+    lockout::record_success(&params.account);
+
+    tracing::info!("user login: {}", params.account);
+    Ok(LoginStep::Token(start_session(db, user, client).await?))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaLoginParams {
+    #[validate(length(min = 1, message = "mfaToken 不能为空"))]
+    pub mfa_token: String,
+    // TOTP 验证码或恢复码
+    #[validate(length(min = 1, max = 32, message = "验证码不能为空"))]
+    pub code: String,
+}
+
+// 登录第二步, 使用临时 token 和验证码换取正式 token
+#[debug_handler]
+async fn login_mfa(
+    State(AppState { db }): State<AppState>,
+    client: ClientInfo,
+    ValidJson(params): ValidJson<MfaLoginParams>,
+) -> ApiResult<ApiResponse<LoginResult>> {
+    let claims = mfa::decode_challenge(&params.mfa_token).await?;
+    let user = find_enabled_user(&db, &claims.principal.id).await?;
+    let account = user.account.clone();
+
+    let result = verify_mfa(&db, &client, &claims, user, &params.code).await;
+    let outcome = result.as_ref().map(|_| login_log::LOGIN_SUCCESS);
+    login_log::record(&db, &account, &client, outcome).await;
+
+    Ok(ApiResponse::ok("ok", Some(result?)))
+}
+
+async fn verify_mfa(
+    db: &DatabaseConnection,
+    client: &ClientInfo,
+    claims: &Claims,
+    user: sys_user::Model,
+    code: &str,
+) -> ApiResult<LoginResult> {
+    lockout::check(&user.account, client.ip)?;
+
+    // 临时 token 只能使用一次, 验证码错误时同样作废, 需要重新输入密码
+    let verified = mfa::verify(db, &user, code).await?;
+    revocation::revoke(claims).await?;
+    if !verified {
+        lockout::record_failure(&user.account, client.ip)?;
+        return Err(ApiError::Unauthenticated(String::from("验证码错误")));
+    }
+    lockout::record_success(&user.account);
+
+    tracing::info!("user login: {}", user.account);
+    start_session(db, user, client).await
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshParams {
+    #[validate(length(min = 1, message = "refreshToken 不能为空"))]
+    pub refresh_token: String,
+}
+
+// 使用 refresh token 换取新的 access token, 旧的 refresh token 随之失效
+// 密码过期后不能再刷新, 需要修改密码后重新登录
+#[debug_handler]
+async fn refresh_token(
+    State(AppState { db }): State<AppState>,
+    ValidJson(params): ValidJson<RefreshParams>,
+) -> ApiResult<ApiResponse<LoginResult>> {
+    let (rotated, refresh_token) = refresh::rotate(&db, &params.refresh_token).await?;
+    let user = find_enabled_user(&db, &rotated.user_id).await?;
+    if password::is_expired(&user) {
+        refresh::revoke_user(&db, &user.id).await?;
+        return Err(ApiError::PasswordExpired);
+    }
+    let (result, claims) = issue_tokens(&db, user, rotated.family_id, refresh_token).await?;
+    session::renew(&db, &claims).await?;
+
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct LogoutParams {
+    // 同时吊销这次登录的 refresh token
+    pub refresh_token: Option<String>,
+}
+
+// 退出登录, 当前 access token 在过期前也不能再使用
+#[debug_handler]
+async fn logout(
+    State(AppState { db }): State<AppState>,
+    claims: Claims,
+    ValidJson(params): ValidJson<LogoutParams>,
+) -> ApiResult<ApiResponse<()>> {
+    revocation::revoke(&claims).await?;
+    if let Some(session_id) = claims.principal.session_id.as_deref() {
+        session::revoke(&db, session_id).await?;
+    }
+    if let Some(refresh_token) = params.refresh_token.as_deref() {
+        refresh::revoke(&db, refresh_token).await?;
+    }
+    tracing::info!("user logout: {}", claims.principal.id);
+
+    Ok(ApiResponse::ok("ok", None))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordParams {
+    #[validate(length(min = 1, max = 20, message = "账号长度1-20"))]
+    pub account: String,
+    #[validate(length(min = 1, message = "原密码不能为空"))]
+    pub old_password: String,
+    #[validate(custom(function = "crate::framework::utils::validation::is_strong_password"))]
+    pub new_password: String,
+}
+
+// 通过原密码修改密码, 不需要登录, 密码过期时也可以使用
+// 修改后已签发的 token 全部失效
+#[debug_handler]
+async fn change_password(
+    State(AppState { db }): State<AppState>,
+    client: ClientInfo,
+    headers: HeaderMap,
+    ValidJson(params): ValidJson<ChangePasswordParams>,
+) -> ApiResult<ApiResponse<()>> {
+    check_captcha(&headers)?;
+    lockout::check(&params.account, client.ip)?;
+
+    let user = match provider::authenticate(&db, &params.account, &params.old_password, false).await? {
+        Some(user) => user,
+        None => {
+            lockout::record_failure(&params.account, client.ip)?;
+            return Err(ApiError::Unauthenticated(String::from("账号或密码错误")));
+        }
+    };
+    lockout::record_success(&params.account);
+    if !user.enabled {
+        return Err(ApiError::Unauthenticated(String::from("账号已被禁用")));
+    }
+    if !provider::is_local(&user) {
+        return Err(ApiError::Biz(String::from("外部认证的账号请在原系统中修改密码")));
+    }
+    password::check_policy(&params.new_password, &user)?;
+    password::ensure_not_reused(&db, &user, &params.new_password).await?;
+
+    let hashed = password::hash(&params.new_password)?;
+    let user_id = user.id.clone();
+    let txn = db.begin().await?;
+    let mut active_model = user.into_active_model();
+    active_model.password = ActiveValue::Set(hashed.clone());
+    active_model.password_updated_at = ActiveValue::Set(Local::now().naive_local());
+    active_model.update(&txn).await?;
+    password::record(&txn, &user_id, &hashed).await?;
+    txn.commit().await?;
+
+    session::revoke_user(&db, &user_id).await?;
+    tracing::info!("user change password: {}", params.account);
+
+    Ok(ApiResponse::ok("ok", None))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ForgotPasswordParams {
+    #[validate(email(message = "邮箱格式不正确"))]
+    pub email: String,
+}
+
+// 发送重置密码邮件
+// 无论邮箱是否存在都返回成功, 查找用户和发送邮件都在后台进行, 避免通过结果或响应时间探测已注册的邮箱
+#[debug_handler]
+async fn forgot_password(
+    State(AppState { db }): State<AppState>,
+    client: ClientInfo,
+    headers: HeaderMap,
+    ValidJson(params): ValidJson<ForgotPasswordParams>,
+) -> ApiResult<ApiResponse<()>> {
+    check_captcha(&headers)?;
+    lockout::throttle_reset(&params.email, client.ip)?;
+
+    tokio::spawn(async move {
+        if let Err(e) = send_reset_mail(&db, &params.email).await {
+            tracing::error!("Fail to send reset password mail to {}: {:?}", params.email, e);
+        }
+    });
+
+    Ok(ApiResponse::ok("如果邮箱已注册, 重置密码邮件已发送", None))
+}
+
+async fn send_reset_mail(db: &DatabaseConnection, email: &str) -> ApiResult<()> {
+    let user = SysUser::find()
+        .filter(sys_user::Column::Email.eq(email))
+        .filter(sys_user::Column::Enabled.eq(true))
+        .filter(sys_user::Column::AuthSource.eq(provider::LOCAL))
+        .one(db)
+        .await?;
+    let Some(user) = user else {
+        tracing::info!("forgot password, email not found: {}", email);
+        return Ok(());
+    };
+
+    let token = reset::issue(db, &user.id).await?;
+    let url = config::get().auth().reset_password().url().replace("{token}", &token);
+    let mail = Mail {
+        to: email.to_string(),
+        subject: String::from("重置密码"),
+        body: format!(
+            "{}, 你好:\n\n请在 {} 分钟内打开下面的链接重置密码, 如果不是你本人操作请忽略这封邮件.\n\n{}\n",
+            user.name,
+            config::get().auth().reset_password().ttl() / 60,
+            url,
+        ),
+    };
+    mail::send(&mail).await?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetPasswordParams {
+    #[validate(length(min = 1, message = "token 不能为空"))]
+    pub token: String,
+    #[validate(custom(function = "crate::framework::utils::validation::is_strong_password"))]
+    pub new_password: String,
+}
+
+// 通过邮件中的 token 重置密码, 已签发的 token 全部失效
+#[debug_handler]
+async fn reset_password(
+    State(AppState { db }): State<AppState>,
+    ValidJson(params): ValidJson<ResetPasswordParams>,
+) -> ApiResult<ApiResponse<()>> {
+    let txn = db.begin().await?;
+    let user_id = reset::consume(&txn, &params.token).await?;
+    let user = SysUser::find_by_id(&user_id)
+        .one(&txn)
+        .await?
+        .filter(|user| user.enabled)
+        .ok_or_else(|| ApiError::Biz(String::from("账号不存在或已被禁用")))?;
+    password::check_policy(&params.new_password, &user)?;
+    password::ensure_not_reused(&txn, &user, &params.new_password).await?;
+
+    let hashed = password::hash(&params.new_password)?;
+    let account = user.account.clone();
+    let mut active_model = user.into_active_model();
+    active_model.password = ActiveValue::Set(hashed.clone());
+    active_model.password_updated_at = ActiveValue::Set(Local::now().naive_local());
+    active_model.update(&txn).await?;
+    password::record(&txn, &user_id, &hashed).await?;
+    txn.commit().await?;
+
+    session::revoke_user(&db, &user_id).await?;
+    lockout::unlock(&account);
+    tracing::info!("user reset password: {}", account);
+
+    Ok(ApiResponse::ok("ok", None))
+}
+
+// 用户被删除或禁用后不再允许刷新
+async fn find_enabled_user(db: &DatabaseConnection, user_id: &str) -> ApiResult<sys_user::Model> {
+    match SysUser::find_by_id(user_id).one(db).await? {
+        Some(user) if user.enabled => Ok(user),
+        _ => {
+            refresh::revoke_user(db, user_id).await?;
+            Err(ApiError::Unauthenticated(String::from("账号不存在或已被禁用")))
+        }
+    }
+}
+
+// 新的登录: 会话 id 同时作为 refresh token 的 family
+async fn start_session(db: &DatabaseConnection, user: sys_user::Model, client: &ClientInfo) -> ApiResult<LoginResult> {
+    let session_id = next_id();
+    let refresh_token = refresh::issue(db, &user.id, Some(session_id.clone())).await?;
+    let (result, claims) = issue_tokens(db, user, session_id, refresh_token).await?;
+    session::create(db, &claims, client).await?;
+    Ok(result)
+}
+
+// 角色写入 token 供前端使用, 权限校验仍以数据库为准
+async fn issue_tokens(
+    db: &DatabaseConnection,
+    user: sys_user::Model,
+    session_id: String,
+    refresh_token: String,
+) -> ApiResult<(LoginResult, Claims)> {
+    let mut roles = Vec::from_iter(Authorities::load(db, &user.id).await?.roles);
+    roles.sort();
+
+    let jwt = get_jwt();
+    let claims = jwt.claims(
+        Principal {
+            roles,
+            session_id: Some(session_id),
+            ..Principal::new(user.id, user.name)
+        },
+        jwt.expiration(),
+    );
+    let access_token = jwt.sign(&claims)?;
+
+    let result = LoginResult {
+        access_token,
+        token_type: "Bearer",
+        expires_in: jwt.expiration().as_secs(),
+        refresh_token,
+        refresh_expires_in: refresh::ttl().as_secs(),
+    };
+    Ok((result, claims))
+}
+
+// 公开签名公钥(JWKS), 不使用 ApiResponse 包装以便其他服务直接使用
+#[debug_handler]
+pub async fn jwks() -> Json<JwkSet> {
+    Json(get_jwt().jwks())
+}
@@ -0,0 +1,180 @@
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use chrono::Local;
+use sea_orm::{prelude::*, ActiveValue, IntoActiveModel};
+use sea_orm::{DeriveIntoActiveModel, PaginatorTrait, QueryOrder};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::entity::sys_dept::ActiveModel;
+use crate::entity::prelude::{SysDept, SysUser};
+use crate::entity::{sys_dept, sys_user};
+use crate::framework::AppState;
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::auth::permission::require_permission;
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::request::param_valid::Path;
+use crate::framework::request::valid::ValidJson;
+use crate::framework::response::ApiResponse;
+use crate::framework::utils::tree::{build_tree, descendant_ids, TreeNode};
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(query_depts).route_layer(require_permission("system:dept:list")))
+        .route("/tree", routing::get(dept_tree).route_layer(require_permission("system:dept:list")))
+        .route("/create", routing::post(create_dept).route_layer(require_permission("system:dept:create")).route_layer(oper_log("部门管理", "新增部门")))
+        .route("/update/{id}", routing::put(update_dept).route_layer(require_permission("system:dept:update")).route_layer(oper_log("部门管理", "修改部门")))
+        .route("/move/{id}", routing::put(move_dept).route_layer(require_permission("system:dept:update")).route_layer(oper_log("部门管理", "移动部门")))
+        .route("/delete/{id}", routing::delete(delete_dept).route_layer(require_permission("system:dept:delete")).route_layer(oper_log("部门管理", "删除部门")))
+}
+
+#[debug_handler]
+async fn query_depts(
+    State(AppState { db }): State<AppState>,
+) -> ApiResult<ApiResponse<Vec<sys_dept::Model>>> {
+    let depts = SysDept::find()
+        .order_by_asc(sys_dept::Column::OrderNum)
+        .order_by_asc(sys_dept::Column::CreatedAt)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(depts)))
+}
+
+#[debug_handler]
+async fn dept_tree(
+    State(AppState { db }): State<AppState>,
+) -> ApiResult<ApiResponse<Vec<TreeNode<sys_dept::Model>>>> {
+    let depts = SysDept::find()
+        .order_by_asc(sys_dept::Column::OrderNum)
+        .order_by_asc(sys_dept::Column::CreatedAt)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(build_tree(depts))))
+}
+
+#[derive(Debug, Deserialize, Validate, DeriveIntoActiveModel)]
+#[serde(rename_all = "camelCase")]
+pub struct DeptParams {
+    pub parent_id: Option<String>,
+    #[validate(length(min = 1, max = 64, message = "部门名称长度1-64"))]
+    pub name: String,
+    #[serde(default)]
+    pub order_num: i32,
+    #[validate(length(max = 64, message = "负责人长度不能超过64"))]
+    pub leader: Option<String>,
+    #[validate(length(max = 32, message = "联系电话长度不能超过32"))]
+    pub phone: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[debug_handler]
+async fn create_dept(
+    State(AppState { db }): State<AppState>,
+    ValidJson(dept_params): ValidJson<DeptParams>
+) -> ApiResult<ApiResponse<sys_dept::Model>> {
+    ensure_parent_valid(&db, None, dept_params.parent_id.as_deref()).await?;
+
+    let result = dept_params.into_active_model().insert(&db).await?;
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[debug_handler]
+async fn update_dept(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+    ValidJson(dept_params): ValidJson<DeptParams>
+) -> ApiResult<ApiResponse<sys_dept::Model>> {
+    SysDept::find_by_id(&id).one(&db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("待修改部门不存在")))?;
+    ensure_parent_valid(&db, Some(&id), dept_params.parent_id.as_deref()).await?;
+
+    let mut active_model = dept_params.into_active_model();
+    active_model.id = ActiveValue::Unchanged(id);
+    active_model.updated_at = ActiveValue::Set(Local::now().naive_local());
+    let result = active_model.update(&db).await?;
+
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveDeptParams {
+    // 为空表示移动为顶级部门
+    pub parent_id: Option<String>,
+    pub order_num: i32,
+}
+
+// 调整上级部门和排序, 用于前端拖拽
+#[debug_handler]
+async fn move_dept(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+    ValidJson(MoveDeptParams { parent_id, order_num }): ValidJson<MoveDeptParams>
+) -> ApiResult<ApiResponse<sys_dept::Model>> {
+    let existed_dept = SysDept::find_by_id(&id).one(&db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("待移动部门不存在")))?;
+    ensure_parent_valid(&db, Some(&id), parent_id.as_deref()).await?;
+
+    let mut active_model = existed_dept.into_active_model();
+    active_model.parent_id = ActiveValue::Set(parent_id);
+    active_model.order_num = ActiveValue::Set(order_num);
+    active_model.updated_at = ActiveValue::Set(Local::now().naive_local());
+    let result = active_model.update(&db).await?;
+
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[debug_handler]
+async fn delete_dept(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<()>> {
+    let existed_dept = SysDept::find_by_id(&id)
+        .one(&db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("待删除部门不存在")))?;
+    let children = SysDept::find()
+        .filter(sys_dept::Column::ParentId.eq(&id))
+        .count(&db)
+        .await?;
+    if children > 0 {
+        return Err(ApiError::Biz(String::from("存在下级部门, 不能删除")));
+    }
+    let users = SysUser::find()
+        .filter(sys_user::Column::DeptId.eq(&id))
+        .count(&db)
+        .await?;
+    if users > 0 {
+        return Err(ApiError::Biz(String::from("部门下存在用户, 不能删除")));
+    }
+
+    // 角色的自定义数据权限通过外键级联删除
+    let result = existed_dept.delete(&db).await?;
+    tracing::info!("delete dept: {}, rows: {}", id, result.rows_affected);
+    Ok(ApiResponse::ok("ok", None))
+}
+
+// 上级部门必须存在, 且不能是自己或自己的下级
+async fn ensure_parent_valid(db: &DatabaseConnection, id: Option<&str>, parent_id: Option<&str>) -> ApiResult<()> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+    SysDept::find_by_id(parent_id).one(db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("上级部门不存在")))?;
+
+    let Some(id) = id else {
+        return Ok(());
+    };
+    let depts = SysDept::find().all(db).await?;
+    if parent_id == id || descendant_ids(&depts, id).contains(parent_id) {
+        return Err(ApiError::Biz(String::from("上级部门不能是自己或下级部门")));
+    }
+    Ok(())
+}
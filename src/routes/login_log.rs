@@ -0,0 +1,97 @@
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, PaginatorTrait, QueryTrait};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::config;
+use crate::entity::{prelude::SysLoginLog, sys_login_log};
+use crate::framework::AppState;
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::auth::{login_log, permission::require_permission};
+use crate::framework::common::{Page, PaginationParams, SortFields};
+use crate::framework::error::ApiResult;
+use crate::framework::request::valid::{ValidJson, ValidQuery};
+use crate::framework::response::ApiResponse;
+use crate::framework::serde::deserialize_optional;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/page", routing::get(page_login_log).route_layer(require_permission("system:loginlog:list")))
+        .route("/purge", routing::post(purge_login_logs).route_layer(require_permission("system:loginlog:delete")).route_layer(oper_log("登录日志", "清理日志")))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginLogQueryParams {
+    account: Option<String>,
+    ip: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    success: Option<bool>,
+    // 登录时间范围, 格式 2025-01-01T00:00:00
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    begin_time: Option<NaiveDateTime>,
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    end_time: Option<NaiveDateTime>,
+
+    #[validate(nested)]
+    #[serde(flatten)]
+    pagination: PaginationParams,
+}
+
+const LOGIN_LOG_SORT_FIELDS: SortFields<sys_login_log::Column> = &[
+    ("account", sys_login_log::Column::Account),
+    ("ip", sys_login_log::Column::Ip),
+    ("createdAt", sys_login_log::Column::CreatedAt),
+];
+
+#[debug_handler]
+async fn page_login_log(
+    State(AppState { db }): State<AppState>,
+    ValidQuery(LoginLogQueryParams {
+        account,
+        ip,
+        success,
+        begin_time,
+        end_time,
+        mut pagination,
+    }): ValidQuery<LoginLogQueryParams>,
+) -> ApiResult<ApiResponse<Page<sys_login_log::Model>>> {
+    let query = SysLoginLog::find()
+        .apply_if(account.as_ref(), |query, account| query.filter(sys_login_log::Column::Account.contains(account)))
+        .apply_if(ip.as_ref(), |query, ip| query.filter(sys_login_log::Column::Ip.eq(ip)))
+        .apply_if(success, |query, success| query.filter(sys_login_log::Column::Success.eq(success)))
+        .apply_if(begin_time, |query, begin_time| query.filter(sys_login_log::Column::CreatedAt.gte(begin_time)))
+        .apply_if(end_time, |query, end_time| query.filter(sys_login_log::Column::CreatedAt.lte(end_time)));
+    let paginator = pagination
+        .apply_sort(query, LOGIN_LOG_SORT_FIELDS, "createdAt,desc")?
+        .paginate(&db, pagination.size);
+
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(pagination.page - 1).await?;
+
+    Ok(ApiResponse::ok("ok", Some(Page::from_pagination(pagination, total, items))))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PurgeParams {
+    // 保留最近几天的日志, 为空时使用配置的保留天数
+    #[validate(range(min = 1, max = 3650, message = "保留天数必须在1到3650之间"))]
+    pub days: Option<u32>,
+}
+
+// 删除超过保留天数的登录日志, 返回删除的条数
+#[debug_handler]
+async fn purge_login_logs(
+    State(AppState { db }): State<AppState>,
+    ValidJson(params): ValidJson<PurgeParams>,
+) -> ApiResult<ApiResponse<u64>> {
+    let days = params.days
+        .unwrap_or_else(|| config::get().audit().login_log_retention_days())
+        .max(1);
+    let deleted = login_log::purge(&db, days).await?;
+    tracing::info!("purge login logs older than {} days: {}", days, deleted);
+
+    Ok(ApiResponse::ok("ok", Some(deleted)))
+}
@@ -0,0 +1,128 @@
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use chrono::Local;
+use sea_orm::{prelude::*, ActiveValue, IntoActiveModel, TransactionTrait};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::entity::{prelude::SysUser, sys_user};
+use crate::framework::AppState;
+use crate::framework::auth::{extractor::require_interactive, lockout, password, provider, session, Principal};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::request::client::ClientInfo;
+use crate::framework::request::valid::ValidJson;
+use crate::framework::response::ApiResponse;
+
+// 当前登录用户查看和修改自己的资料, 不需要用户管理权限, 也不能通过 API key 访问
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(get_profile))
+        .route("/", routing::put(update_profile).route_layer(oper_log("个人中心", "修改资料")))
+        .route("/password", routing::post(change_password).route_layer(oper_log("个人中心", "修改密码")))
+        .route_layer(require_interactive())
+}
+
+#[debug_handler]
+async fn get_profile(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+) -> ApiResult<ApiResponse<sys_user::Model>> {
+    let user = current_user(&db, &principal).await?;
+
+    Ok(ApiResponse::ok("ok", Some(user)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileParams {
+    #[validate(length(min = 2, max = 20, message = "姓名长度2-20"))]
+    pub name: String,
+    #[validate(custom(function = "crate::framework::utils::validation::is_mobile_phone"))]
+    pub mobile_phone: String,
+    pub birthday: Date,
+}
+
+#[debug_handler]
+async fn update_profile(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    ValidJson(params): ValidJson<ProfileParams>,
+) -> ApiResult<ApiResponse<sys_user::Model>> {
+    let user = current_user(&db, &principal).await?;
+    // 姓名唯一
+    let duplicated = SysUser::find()
+        .filter(sys_user::Column::Name.eq(&params.name))
+        .filter(sys_user::Column::Id.ne(&user.id))
+        .one(&db)
+        .await?;
+    if duplicated.is_some() {
+        return Err(ApiError::Biz(String::from("姓名已被使用")));
+    }
+
+    let mut active_model = user.into_active_model();
+    active_model.name = ActiveValue::Set(params.name);
+    active_model.mobile_phone = ActiveValue::Set(params.mobile_phone);
+    active_model.birthday = ActiveValue::Set(params.birthday);
+    active_model.updated_at = ActiveValue::Set(Local::now().naive_local());
+    let user = active_model.update(&db).await?;
+    tracing::info!("user update profile: {}", user.account);
+
+    Ok(ApiResponse::ok("ok", Some(user)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordParams {
+    #[validate(length(min = 1, message = "原密码不能为空"))]
+    pub old_password: String,
+    #[validate(custom(function = "crate::framework::utils::validation::is_strong_password"))]
+    pub new_password: String,
+}
+
+// 通过原密码修改自己的密码, 修改后所有登录(包括当前登录)都需要重新登录
+// 原密码错误与登录失败一样计数, 避免 token 泄露后被用来猜测密码
+#[debug_handler]
+async fn change_password(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    client: ClientInfo,
+    ValidJson(params): ValidJson<ChangePasswordParams>,
+) -> ApiResult<ApiResponse<()>> {
+    let user = current_user(&db, &principal).await?;
+    if !provider::is_local(&user) {
+        return Err(ApiError::Biz(String::from("外部认证的账号请在原系统中修改密码")));
+    }
+    lockout::check(&user.account, client.ip)?;
+    if !bcrypt::verify(&params.old_password, &user.password)? {
+        lockout::record_failure(&user.account, client.ip)?;
+        return Err(ApiError::Biz(String::from("原密码错误")));
+    }
+    lockout::record_success(&user.account);
+    password::check_policy(&params.new_password, &user)?;
+    password::ensure_not_reused(&db, &user, &params.new_password).await?;
+
+    let hashed = password::hash(&params.new_password)?;
+    let user_id = user.id.clone();
+    let account = user.account.clone();
+    let txn = db.begin().await?;
+    let mut active_model = user.into_active_model();
+    active_model.password = ActiveValue::Set(hashed.clone());
+    active_model.password_updated_at = ActiveValue::Set(Local::now().naive_local());
+    active_model.update(&txn).await?;
+    password::record(&txn, &user_id, &hashed).await?;
+    txn.commit().await?;
+
+    session::revoke_user(&db, &user_id).await?;
+    tracing::info!("user change password: {}", account);
+
+    Ok(ApiResponse::ok("ok", None))
+}
+
+async fn current_user(db: &DatabaseConnection, principal: &Principal) -> ApiResult<sys_user::Model> {
+    SysUser::find_by_id(&principal.id)
+        .one(db)
+        .await?
+        .filter(|user| user.enabled)
+        .ok_or_else(|| ApiError::Unauthenticated(String::from("账号不存在或已被禁用")))
+}
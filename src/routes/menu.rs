@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use chrono::Local;
+use sea_orm::{prelude::*, ActiveValue, IntoActiveModel, JoinType, QuerySelect};
+use sea_orm::{DeriveIntoActiveModel, PaginatorTrait, QueryOrder};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::entity::sys_menu::ActiveModel;
+use crate::entity::prelude::SysMenu;
+use crate::entity::{sys_menu, sys_role, sys_role_menu};
+use crate::enums::MenuType;
+use crate::framework::AppState;
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::auth::permission::{require_permission, Authorities};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::request::param_valid::Path;
+use crate::framework::request::valid::ValidJson;
+use crate::framework::response::ApiResponse;
+use crate::framework::utils::tree::{build_tree, descendant_ids, TreeNode};
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(query_menus).route_layer(require_permission("system:menu:list")))
+        .route("/tree", routing::get(menu_tree).route_layer(require_permission("system:menu:list")))
+        // 当前用户的导航菜单, 登录即可访问
+        .route("/current", routing::get(current_menus))
+        .route("/create", routing::post(create_menu).route_layer(require_permission("system:menu:create")).route_layer(oper_log("菜单管理", "新增菜单")))
+        .route("/update/{id}", routing::put(update_menu).route_layer(require_permission("system:menu:update")).route_layer(oper_log("菜单管理", "修改菜单")))
+        .route("/delete/{id}", routing::delete(delete_menu).route_layer(require_permission("system:menu:delete")).route_layer(oper_log("菜单管理", "删除菜单")))
+}
+
+#[debug_handler]
+async fn query_menus(
+    State(AppState { db }): State<AppState>,
+) -> ApiResult<ApiResponse<Vec<sys_menu::Model>>> {
+    let menus = SysMenu::find()
+        .order_by_asc(sys_menu::Column::OrderNum)
+        .order_by_asc(sys_menu::Column::CreatedAt)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(menus)))
+}
+
+#[debug_handler]
+async fn menu_tree(
+    State(AppState { db }): State<AppState>,
+) -> ApiResult<ApiResponse<Vec<TreeNode<sys_menu::Model>>>> {
+    let menus = SysMenu::find()
+        .order_by_asc(sys_menu::Column::OrderNum)
+        .order_by_asc(sys_menu::Column::CreatedAt)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(build_tree(menus))))
+}
+
+// 按角色过滤后的目录和菜单, 按钮和隐藏的菜单不参与导航
+// 超级管理员可以看到所有可见的菜单
+#[debug_handler]
+async fn current_menus(
+    State(AppState { db }): State<AppState>,
+    authorities: Authorities,
+) -> ApiResult<ApiResponse<Vec<TreeNode<sys_menu::Model>>>> {
+    let mut query = SysMenu::find()
+        .filter(sys_menu::Column::MenuType.ne(MenuType::Button))
+        .filter(sys_menu::Column::Visible.eq(true));
+    if !authorities.is_super_admin() {
+        query = query
+            .join(JoinType::InnerJoin, sys_menu::Relation::SysRoleMenu.def())
+            .join(JoinType::InnerJoin, sys_role_menu::Relation::SysRole.def())
+            .filter(sys_role::Column::Code.is_in(&authorities.roles))
+            .distinct();
+    }
+    let mut menus = query
+        .order_by_asc(sys_menu::Column::OrderNum)
+        .order_by_asc(sys_menu::Column::CreatedAt)
+        .all(&db)
+        .await?;
+
+    // 隐藏目录下的菜单同样隐藏, 否则会被当作根节点返回
+    let hidden = SysMenu::find().filter(sys_menu::Column::Visible.eq(false)).all(&db).await?;
+    if !hidden.is_empty() {
+        let all = SysMenu::find().all(&db).await?;
+        let hidden_ids: HashSet<String> = hidden
+            .iter()
+            .flat_map(|menu| descendant_ids(&all, &menu.id))
+            .collect();
+        menus.retain(|menu| !hidden_ids.contains(&menu.id));
+    }
+
+    Ok(ApiResponse::ok("ok", Some(build_tree(menus))))
+}
+
+#[derive(Debug, Deserialize, Validate, DeriveIntoActiveModel)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuParams {
+    pub parent_id: Option<String>,
+    pub menu_type: MenuType,
+    #[validate(length(min = 1, max = 64, message = "菜单名称长度1-64"))]
+    pub name: String,
+    #[validate(length(max = 255, message = "路由地址长度不能超过255"))]
+    pub path: Option<String>,
+    #[validate(length(max = 255, message = "组件路径长度不能超过255"))]
+    pub component: Option<String>,
+    #[validate(length(max = 64, message = "图标长度不能超过64"))]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub order_num: i32,
+    #[validate(custom(function = "crate::framework::utils::validation::is_permission_code"))]
+    pub permission: Option<String>,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+#[debug_handler]
+async fn create_menu(
+    State(AppState { db }): State<AppState>,
+    ValidJson(menu_params): ValidJson<MenuParams>
+) -> ApiResult<ApiResponse<sys_menu::Model>> {
+    ensure_parent_valid(&db, None, menu_params.parent_id.as_deref()).await?;
+
+    let result = menu_params.into_active_model().insert(&db).await?;
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[debug_handler]
+async fn update_menu(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+    ValidJson(menu_params): ValidJson<MenuParams>
+) -> ApiResult<ApiResponse<sys_menu::Model>> {
+    SysMenu::find_by_id(&id).one(&db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("待修改菜单不存在")))?;
+    ensure_parent_valid(&db, Some(&id), menu_params.parent_id.as_deref()).await?;
+
+    let mut active_model = menu_params.into_active_model();
+    active_model.id = ActiveValue::Unchanged(id);
+    active_model.updated_at = ActiveValue::Set(Local::now().naive_local());
+    let result = active_model.update(&db).await?;
+
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[debug_handler]
+async fn delete_menu(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<()>> {
+    let existed_menu = SysMenu::find_by_id(&id)
+        .one(&db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("待删除菜单不存在")))?;
+    let children = SysMenu::find()
+        .filter(sys_menu::Column::ParentId.eq(&id))
+        .count(&db)
+        .await?;
+    if children > 0 {
+        return Err(ApiError::Biz(String::from("存在子菜单, 不能删除")));
+    }
+
+    // 角色的关联通过外键级联删除
+    let result = existed_menu.delete(&db).await?;
+    tracing::info!("delete menu: {}, rows: {}", id, result.rows_affected);
+    Ok(ApiResponse::ok("ok", None))
+}
+
+// 上级菜单必须存在, 不能是按钮, 也不能是自己或自己的下级
+async fn ensure_parent_valid(db: &DatabaseConnection, id: Option<&str>, parent_id: Option<&str>) -> ApiResult<()> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+    let parent = SysMenu::find_by_id(parent_id).one(db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("上级菜单不存在")))?;
+    if parent.menu_type == MenuType::Button {
+        return Err(ApiError::Biz(String::from("按钮不能作为上级菜单")));
+    }
+
+    let Some(id) = id else {
+        return Ok(());
+    };
+    let mut current = Some(parent);
+    while let Some(menu) = current {
+        if menu.id == id {
+            return Err(ApiError::Biz(String::from("上级菜单不能是自己或下级菜单")));
+        }
+        current = match menu.parent_id {
+            Some(parent_id) => SysMenu::find_by_id(parent_id).one(db).await?,
+            None => None,
+        };
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entity::{prelude::SysUser, sys_user};
+use crate::framework::AppState;
+use crate::framework::auth::{extractor::require_interactive, lockout, mfa::{self, Enrollment}, Principal};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::request::client::ClientInfo;
+use crate::framework::request::valid::ValidJson;
+use crate::framework::response::ApiResponse;
+
+// 当前登录用户管理自己的两步验证, 不能通过 API key 访问
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(mfa_status))
+        .route("/setup", routing::post(setup_mfa).route_layer(oper_log("两步验证", "生成秘钥")))
+        .route("/confirm", routing::post(confirm_mfa).route_layer(oper_log("两步验证", "开启两步验证")))
+        .route("/recovery-codes", routing::post(regenerate_recovery_codes).route_layer(oper_log("两步验证", "重新生成恢复码")))
+        .route("/disable", routing::post(disable_mfa).route_layer(oper_log("两步验证", "关闭两步验证")))
+        .route_layer(require_interactive())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaStatus {
+    pub enabled: bool,
+    pub recovery_codes_remaining: u64,
+}
+
+#[debug_handler]
+async fn mfa_status(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+) -> ApiResult<ApiResponse<MfaStatus>> {
+    let enabled = mfa::is_enabled(&db, &principal.id).await?;
+    let recovery_codes_remaining = if enabled {
+        mfa::remaining_recovery_codes(&db, &principal.id).await?
+    } else {
+        0
+    };
+
+    Ok(ApiResponse::ok("ok", Some(MfaStatus { enabled, recovery_codes_remaining })))
+}
+
+// 生成秘钥和二维码, 使用验证器 App 扫码后调用 confirm 开启
+#[debug_handler]
+async fn setup_mfa(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+) -> ApiResult<ApiResponse<Enrollment>> {
+    let user = current_user(&db, &principal).await?;
+    let enrollment = mfa::setup(&db, &user).await?;
+
+    Ok(ApiResponse::ok("ok", Some(enrollment)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MfaCodeParams {
+    #[validate(length(min = 1, max = 32, message = "验证码不能为空"))]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodes {
+    // 只返回这一次, 需要用户自行保存
+    pub recovery_codes: Vec<String>,
+}
+
+#[debug_handler]
+async fn confirm_mfa(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    ValidJson(params): ValidJson<MfaCodeParams>,
+) -> ApiResult<ApiResponse<RecoveryCodes>> {
+    let user = current_user(&db, &principal).await?;
+    let recovery_codes = mfa::confirm(&db, &user, &params.code).await?;
+    tracing::info!("mfa enabled: {}", user.account);
+
+    Ok(ApiResponse::ok("ok", Some(RecoveryCodes { recovery_codes })))
+}
+
+// 重新生成恢复码, 需要验证码确认
+#[debug_handler]
+async fn regenerate_recovery_codes(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    client: ClientInfo,
+    ValidJson(params): ValidJson<MfaCodeParams>,
+) -> ApiResult<ApiResponse<RecoveryCodes>> {
+    let user = current_user(&db, &principal).await?;
+    verify_code(&db, &user, &client, &params.code).await?;
+    let recovery_codes = mfa::regenerate_recovery_codes(&db, &user.id).await?;
+
+    Ok(ApiResponse::ok("ok", Some(RecoveryCodes { recovery_codes })))
+}
+
+// 关闭两步验证, 需要验证码或恢复码确认
+#[debug_handler]
+async fn disable_mfa(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    client: ClientInfo,
+    ValidJson(params): ValidJson<MfaCodeParams>,
+) -> ApiResult<ApiResponse<()>> {
+    let user = current_user(&db, &principal).await?;
+    verify_code(&db, &user, &client, &params.code).await?;
+    mfa::disable(&db, &user.id).await?;
+    tracing::info!("mfa disabled: {}", user.account);
+
+    Ok(ApiResponse::ok("ok", None))
+}
+
+// 与登录第二步相同, 验证码错误计入登录失败次数, 避免 token 泄露后被用来暴力猜测验证码
+async fn verify_code(db: &DatabaseConnection, user: &sys_user::Model, client: &ClientInfo, code: &str) -> ApiResult<()> {
+    lockout::check(&user.account, client.ip)?;
+    if !mfa::verify(db, user, code).await? {
+        lockout::record_failure(&user.account, client.ip)?;
+        return Err(ApiError::Biz(String::from("验证码错误")));
+    }
+    lockout::record_success(&user.account);
+    Ok(())
+}
+
+async fn current_user(db: &DatabaseConnection, principal: &Principal) -> ApiResult<sys_user::Model> {
+    SysUser::find_by_id(&principal.id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Unauthenticated(String::from("账号不存在或已被禁用")))
+}
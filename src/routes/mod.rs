@@ -1,15 +1,42 @@
-use axum::Router;
+use axum::{routing, Router};
 
 use crate::{framework::AppState, framework::error::{ApiError, ApiResult}};
+use crate::framework::auth::extractor::require_login;
 
+pub mod api_key;
+pub mod auth;
+pub mod dept;
+pub mod login_log;
+pub mod me;
+pub mod menu;
+pub mod mfa;
+pub mod oper_log;
+pub mod permission;
+pub mod role;
+pub mod session;
 pub mod user;
 
 pub fn create_router() -> Router<AppState> {
     Router::new()
+        .route("/.well-known/jwks.json", routing::get(auth::jwks))
         .nest(
             "/api",
             Router::new()
+            // 需要登录才能访问的路由
+            .nest("/me", me::create_router())
             .nest("/users", user::create_router())
+            .nest("/roles", role::create_router())
+            .nest("/permissions", permission::create_router())
+            .nest("/menus", menu::create_router())
+            .nest("/depts", dept::create_router())
+            .nest("/mfa", mfa::create_router())
+            .nest("/api-keys", api_key::create_router())
+            .nest("/sessions", session::create_router())
+            .nest("/login-logs", login_log::create_router())
+            .nest("/oper-logs", oper_log::create_router())
+            .route_layer(require_login())
+            // 公开路由
+            .nest("/auth", auth::create_router())
             .fallback(async || -> ApiResult<()> {
                     tracing::warn!("Not Found");
                     Err(ApiError::NotFound)
@@ -0,0 +1,112 @@
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use chrono::NaiveDateTime;
+use sea_orm::{prelude::*, Condition, PaginatorTrait, QueryTrait};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::config;
+use crate::entity::{prelude::SysOperLog, sys_oper_log};
+use crate::framework::AppState;
+use crate::framework::auth::permission::require_permission;
+use crate::framework::common::{Page, PaginationParams, SortFields};
+use crate::framework::error::ApiResult;
+use crate::framework::middleware::oper_log::{self, oper_log};
+use crate::framework::request::valid::{ValidJson, ValidQuery};
+use crate::framework::response::ApiResponse;
+use crate::framework::serde::deserialize_optional;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/page", routing::get(page_oper_log).route_layer(require_permission("system:operlog:list")))
+        .route("/purge", routing::post(purge_oper_logs).route_layer(require_permission("system:operlog:delete")).route_layer(oper_log("操作日志", "清理日志")))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct OperLogQueryParams {
+    module: Option<String>,
+    action: Option<String>,
+    // 操作人 id 或名称
+    user: Option<String>,
+    // 请求路径, 如 /api/users/delete/2
+    path: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    success: Option<bool>,
+    // 操作时间范围, 格式 2025-01-01T00:00:00
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    begin_time: Option<NaiveDateTime>,
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    end_time: Option<NaiveDateTime>,
+
+    #[validate(nested)]
+    #[serde(flatten)]
+    pagination: PaginationParams,
+}
+
+const OPER_LOG_SORT_FIELDS: SortFields<sys_oper_log::Column> = &[
+    ("module", sys_oper_log::Column::Module),
+    ("latency", sys_oper_log::Column::Latency),
+    ("status", sys_oper_log::Column::Status),
+    ("createdAt", sys_oper_log::Column::CreatedAt),
+];
+
+#[debug_handler]
+async fn page_oper_log(
+    State(AppState { db }): State<AppState>,
+    ValidQuery(OperLogQueryParams {
+        module,
+        action,
+        user,
+        path,
+        success,
+        begin_time,
+        end_time,
+        mut pagination,
+    }): ValidQuery<OperLogQueryParams>,
+) -> ApiResult<ApiResponse<Page<sys_oper_log::Model>>> {
+    let query = SysOperLog::find()
+        .apply_if(module.as_ref(), |query, module| query.filter(sys_oper_log::Column::Module.eq(module)))
+        .apply_if(action.as_ref(), |query, action| query.filter(sys_oper_log::Column::Action.eq(action)))
+        .apply_if(user.as_ref(), |query, user| {
+            query.filter(
+                Condition::any()
+                    .add(sys_oper_log::Column::UserId.eq(user))
+                    .add(sys_oper_log::Column::UserName.contains(user)),
+            )
+        })
+        .apply_if(path.as_ref(), |query, path| query.filter(sys_oper_log::Column::Path.contains(path)))
+        .apply_if(success, |query, success| query.filter(sys_oper_log::Column::Success.eq(success)))
+        .apply_if(begin_time, |query, begin_time| query.filter(sys_oper_log::Column::CreatedAt.gte(begin_time)))
+        .apply_if(end_time, |query, end_time| query.filter(sys_oper_log::Column::CreatedAt.lte(end_time)));
+    let paginator = pagination
+        .apply_sort(query, OPER_LOG_SORT_FIELDS, "createdAt,desc")?
+        .paginate(&db, pagination.size);
+
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(pagination.page - 1).await?;
+
+    Ok(ApiResponse::ok("ok", Some(Page::from_pagination(pagination, total, items))))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PurgeParams {
+    // 保留最近几天的日志, 为空时使用配置的保留天数
+    #[validate(range(min = 1, max = 3650, message = "保留天数必须在1到3650之间"))]
+    pub days: Option<u32>,
+}
+
+// 删除超过保留天数的操作日志, 返回删除的条数
+#[debug_handler]
+async fn purge_oper_logs(
+    State(AppState { db }): State<AppState>,
+    ValidJson(params): ValidJson<PurgeParams>,
+) -> ApiResult<ApiResponse<u64>> {
+    let days = params.days
+        .unwrap_or_else(|| config::get().audit().oper_log_retention_days())
+        .max(1);
+    let deleted = oper_log::purge(&db, days).await?;
+    tracing::info!("purge oper logs older than {} days: {}", days, deleted);
+
+    Ok(ApiResponse::ok("ok", Some(deleted)))
+}
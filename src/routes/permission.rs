@@ -0,0 +1,104 @@
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use chrono::Local;
+use sea_orm::{prelude::*, ActiveValue, IntoActiveModel};
+use sea_orm::{DeriveIntoActiveModel, PaginatorTrait, QueryOrder, QueryTrait};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::entity::sys_permission::ActiveModel;
+use crate::entity::{prelude::SysPermission, sys_permission};
+use crate::framework::AppState;
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::auth::permission::require_permission;
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::request::param_valid::Path;
+use crate::framework::request::valid::ValidJson;
+use crate::framework::response::ApiResponse;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(query_permissions).route_layer(require_permission("system:permission:list")))
+        .route("/create", routing::post(create_permission).route_layer(require_permission("system:permission:create")).route_layer(oper_log("权限管理", "新增权限")))
+        .route("/update/{id}", routing::put(update_permission).route_layer(require_permission("system:permission:update")).route_layer(oper_log("权限管理", "修改权限")))
+        .route("/delete/{id}", routing::delete(delete_permission).route_layer(require_permission("system:permission:delete")).route_layer(oper_log("权限管理", "删除权限")))
+}
+
+#[debug_handler]
+async fn query_permissions(
+    State(AppState { db }): State<AppState>,
+) -> ApiResult<ApiResponse<Vec<sys_permission::Model>>> {
+    let permissions = SysPermission::find()
+        .order_by_asc(sys_permission::Column::Code)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(permissions)))
+}
+
+#[derive(Debug, Deserialize, Validate, DeriveIntoActiveModel)]
+pub struct PermissionParams {
+    // 权限标识, 如 system:user:delete
+    #[validate(custom(function = "crate::framework::utils::validation::is_permission_code"))]
+    pub code: String,
+    #[validate(length(min = 1, max = 64, message = "权限名称长度1-64"))]
+    pub name: String,
+    #[validate(length(max = 255, message = "描述长度不能超过255"))]
+    pub description: Option<String>,
+}
+
+#[debug_handler]
+async fn create_permission(
+    State(AppState { db }): State<AppState>,
+    ValidJson(permission_params): ValidJson<PermissionParams>
+) -> ApiResult<ApiResponse<sys_permission::Model>> {
+    ensure_code_unique(&db, &permission_params.code, None).await?;
+
+    let result = permission_params.into_active_model().insert(&db).await?;
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[debug_handler]
+async fn update_permission(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+    ValidJson(permission_params): ValidJson<PermissionParams>
+) -> ApiResult<ApiResponse<sys_permission::Model>> {
+    SysPermission::find_by_id(&id).one(&db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("待修改权限不存在")))?;
+    ensure_code_unique(&db, &permission_params.code, Some(&id)).await?;
+
+    let mut active_model = permission_params.into_active_model();
+    active_model.id = ActiveValue::Unchanged(id);
+    active_model.updated_at = ActiveValue::Set(Local::now().naive_local());
+    let result = active_model.update(&db).await?;
+
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[debug_handler]
+async fn delete_permission(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<()>> {
+    let existed_permission = SysPermission::find_by_id(&id)
+        .one(&db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("待删除权限不存在")))?;
+
+    let result = existed_permission.delete(&db).await?;
+    tracing::info!("delete permission: {}, rows: {}", id, result.rows_affected);
+    Ok(ApiResponse::ok("ok", None))
+}
+
+async fn ensure_code_unique(db: &DatabaseConnection, code: &str, exclude_id: Option<&str>) -> ApiResult<()> {
+    let count = SysPermission::find()
+        .filter(sys_permission::Column::Code.eq(code))
+        .apply_if(exclude_id, |query, id| query.filter(sys_permission::Column::Id.ne(id)))
+        .count(db)
+        .await?;
+    if count > 0 {
+        return Err(ApiError::Biz(String::from("权限标识已存在")));
+    }
+    Ok(())
+}
@@ -0,0 +1,435 @@
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use chrono::Local;
+use sea_orm::{prelude::*, ActiveValue, IntoActiveModel, JoinType, QuerySelect, TransactionTrait};
+use sea_orm::{Condition, DeriveIntoActiveModel, PaginatorTrait, QueryOrder, QueryTrait};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entity::sys_role::ActiveModel;
+use crate::entity::prelude::{SysDept, SysMenu, SysPermission, SysRole, SysRoleDept, SysRoleMenu, SysRolePermission, SysUserRole};
+use crate::entity::{sys_dept, sys_menu, sys_permission, sys_role, sys_role_dept, sys_role_menu, sys_role_permission, sys_user_role};
+use crate::enums::DataScope;
+use crate::framework::AppState;
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::auth::{data_scope::DataPermission, Principal};
+use crate::framework::auth::permission::{require_permission, Authorities, SUPER_ADMIN};
+use crate::framework::common::{Page, PaginationParams, SortFields};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::request::filter::{self, Filter, FilterField, FilterFields};
+use crate::framework::request::param_valid::Path;
+use crate::framework::request::valid::{ValidJson, ValidQuery};
+use crate::framework::response::ApiResponse;
+
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/", routing::get(query_roles).route_layer(require_permission("system:role:list")))
+        .route("/page", routing::get(page_role).route_layer(require_permission("system:role:list")))
+        .route("/create", routing::post(create_role).route_layer(require_permission("system:role:create")).route_layer(oper_log("角色管理", "新增角色")))
+        .route("/update/{id}", routing::put(update_role).route_layer(require_permission("system:role:update")).route_layer(oper_log("角色管理", "修改角色")))
+        .route("/delete/{id}", routing::delete(delete_role).route_layer(require_permission("system:role:delete")).route_layer(oper_log("角色管理", "删除角色")))
+        .route("/permissions/{id}", routing::get(role_permissions).route_layer(require_permission("system:role:list")))
+        .route("/permissions/{id}", routing::put(assign_permissions).route_layer(require_permission("system:role:permission")).route_layer(oper_log("角色管理", "分配权限")))
+        .route("/menus/{id}", routing::get(role_menus).route_layer(require_permission("system:role:list")))
+        .route("/menus/{id}", routing::put(assign_menus).route_layer(require_permission("system:role:menu")).route_layer(oper_log("角色管理", "分配菜单")))
+        .route("/data-scope/{id}", routing::get(role_data_scope).route_layer(require_permission("system:role:list")))
+        .route("/data-scope/{id}", routing::put(assign_data_scope).route_layer(require_permission("system:role:scope")).route_layer(oper_log("角色管理", "分配数据权限")))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleQueryParams {
+    keyword: Option<String>,
+
+    #[validate(nested)]
+    #[serde(flatten)]
+    pagination: PaginationParams,
+
+    // 其余参数按 code[like]=admin&enabled=true 解析
+    #[serde(flatten)]
+    filters: Filter<RoleFilterFields>,
+}
+
+#[derive(Debug)]
+pub struct RoleFilterFields;
+
+impl FilterFields for RoleFilterFields {
+    type Column = sys_role::Column;
+    const FIELDS: &'static [FilterField<Self::Column>] = &[
+        FilterField::new("code", sys_role::Column::Code, filter::TEXT),
+        FilterField::new("name", sys_role::Column::Name, filter::TEXT),
+        FilterField::new("enabled", sys_role::Column::Enabled, filter::BOOL),
+        FilterField::new("dataScope", sys_role::Column::DataScope, filter::ENUM).enumeration::<DataScope>(),
+        FilterField::new("createdAt", sys_role::Column::CreatedAt, filter::RANGE),
+    ];
+}
+
+const ROLE_SORT_FIELDS: SortFields<sys_role::Column> = &[
+    ("code", sys_role::Column::Code),
+    ("name", sys_role::Column::Name),
+    ("enabled", sys_role::Column::Enabled),
+    ("createdAt", sys_role::Column::CreatedAt),
+    ("updatedAt", sys_role::Column::UpdatedAt),
+];
+
+#[debug_handler]
+async fn query_roles(
+    State(AppState { db }): State<AppState>,
+) -> ApiResult<ApiResponse<Vec<sys_role::Model>>> {
+    let roles = SysRole::find()
+        .order_by_asc(sys_role::Column::CreatedAt)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(roles)))
+}
+
+#[debug_handler]
+async fn page_role(
+    State(AppState { db }): State<AppState>,
+    ValidQuery(RoleQueryParams {
+        keyword,
+        mut pagination,
+        filters,
+    }): ValidQuery<RoleQueryParams>
+) -> ApiResult<ApiResponse<Page<sys_role::Model>>> {
+    let query = SysRole::find()
+        .filter(filters.condition())
+        .apply_if(keyword.as_ref(), |query, keyword| {
+            query.filter(
+                Condition::any()
+                    .add(sys_role::Column::Code.contains(keyword))
+                    .add(sys_role::Column::Name.contains(keyword)),
+            )
+        });
+    let paginator = pagination
+        .apply_sort(query, ROLE_SORT_FIELDS, "createdAt,desc")?
+        .paginate(&db, pagination.size);
+
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(pagination.page - 1).await?;
+    let page = Page::from_pagination(pagination, total, items);
+
+    Ok(ApiResponse::ok("ok", Some(page)))
+}
+
+#[derive(Debug, Deserialize, Validate, DeriveIntoActiveModel)]
+pub struct RoleParams {
+    #[validate(length(min = 1, max = 64, message = "角色编码长度1-64"))]
+    pub code: String,
+    #[validate(length(min = 1, max = 64, message = "角色名称长度1-64"))]
+    pub name: String,
+    #[validate(length(max = 255, message = "描述长度不能超过255"))]
+    pub description: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[debug_handler]
+async fn create_role(
+    State(AppState { db }): State<AppState>,
+    ValidJson(role_params): ValidJson<RoleParams>
+) -> ApiResult<ApiResponse<sys_role::Model>> {
+    ensure_code_unique(&db, &role_params.code, None).await?;
+
+    let result = role_params.into_active_model().insert(&db).await?;
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[debug_handler]
+async fn update_role(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    authorities: Authorities,
+    Path(id): Path<String>,
+    ValidJson(role_params): ValidJson<RoleParams>
+) -> ApiResult<ApiResponse<sys_role::Model>> {
+    let existed_role = SysRole::find_by_id(&id).one(&db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("待修改角色不存在")))?;
+    ensure_role_editable(&db, &principal, &authorities, &existed_role).await?;
+    // 内置角色不能改编码或禁用
+    if existed_role.code == SUPER_ADMIN && (role_params.code != SUPER_ADMIN || !role_params.enabled) {
+        return Err(ApiError::Biz(String::from("内置角色不能修改编码或禁用")));
+    }
+    ensure_code_unique(&db, &role_params.code, Some(&id)).await?;
+
+    let mut active_model = role_params.into_active_model();
+    active_model.id = ActiveValue::Unchanged(id);
+    active_model.updated_at = ActiveValue::Set(Local::now().naive_local());
+    let result = active_model.update(&db).await?;
+
+    Ok(ApiResponse::ok("ok", Some(result)))
+}
+
+#[debug_handler]
+async fn delete_role(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<()>> {
+    let existed_role = SysRole::find_by_id(&id)
+        .one(&db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("待删除角色不存在")))?;
+    if existed_role.code == SUPER_ADMIN {
+        return Err(ApiError::Biz(String::from("内置角色不能删除")));
+    }
+
+    // 用户、权限的关联通过外键级联删除
+    let result = existed_role.delete(&db).await?;
+    tracing::info!("delete role: {}, rows: {}", id, result.rows_affected);
+    Ok(ApiResponse::ok("ok", None))
+}
+
+#[debug_handler]
+async fn role_permissions(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<Vec<sys_permission::Model>>> {
+    let permissions = SysPermission::find()
+        .join(JoinType::InnerJoin, sys_permission::Relation::SysRolePermission.def())
+        .filter(sys_role_permission::Column::RoleId.eq(id))
+        .order_by_asc(sys_permission::Column::Code)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(permissions)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignPermissionParams {
+    pub permission_ids: Vec<String>,
+}
+
+// 覆盖角色的权限
+#[debug_handler]
+async fn assign_permissions(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    authorities: Authorities,
+    Path(id): Path<String>,
+    ValidJson(AssignPermissionParams { mut permission_ids }): ValidJson<AssignPermissionParams>,
+) -> ApiResult<ApiResponse<()>> {
+    let role = SysRole::find_by_id(&id).one(&db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("角色不存在")))?;
+    ensure_role_editable(&db, &principal, &authorities, &role).await?;
+    permission_ids.sort();
+    permission_ids.dedup();
+    let permissions = SysPermission::find()
+        .filter(sys_permission::Column::Id.is_in(&permission_ids))
+        .all(&db)
+        .await?;
+    if permissions.len() != permission_ids.len() {
+        return Err(ApiError::Biz(String::from("权限不存在")));
+    }
+    // 只能分配自己拥有的权限
+    if !authorities.is_super_admin()
+        && let Some(permission) = permissions.iter().find(|permission| !authorities.permissions.contains(&permission.code))
+    {
+        return Err(ApiError::Forbidden(format!("不能分配超出自身权限的权限: {}", permission.code)));
+    }
+
+    let txn = db.begin().await?;
+    SysRolePermission::delete_many()
+        .filter(sys_role_permission::Column::RoleId.eq(&id))
+        .exec(&txn)
+        .await?;
+    if !permission_ids.is_empty() {
+        SysRolePermission::insert_many(permission_ids.into_iter().map(|permission_id| {
+            sys_role_permission::ActiveModel {
+                role_id: ActiveValue::Set(id.clone()),
+                permission_id: ActiveValue::Set(permission_id),
+            }
+        }))
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    Ok(ApiResponse::ok("ok", None))
+}
+
+#[debug_handler]
+async fn role_menus(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<Vec<sys_menu::Model>>> {
+    let menus = SysMenu::find()
+        .join(JoinType::InnerJoin, sys_menu::Relation::SysRoleMenu.def())
+        .filter(sys_role_menu::Column::RoleId.eq(id))
+        .order_by_asc(sys_menu::Column::OrderNum)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(menus)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignMenuParams {
+    pub menu_ids: Vec<String>,
+}
+
+// 覆盖角色的菜单
+#[debug_handler]
+async fn assign_menus(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+    ValidJson(AssignMenuParams { mut menu_ids }): ValidJson<AssignMenuParams>,
+) -> ApiResult<ApiResponse<()>> {
+    SysRole::find_by_id(&id).one(&db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("角色不存在")))?;
+    menu_ids.sort();
+    menu_ids.dedup();
+    let count = SysMenu::find()
+        .filter(sys_menu::Column::Id.is_in(&menu_ids))
+        .count(&db)
+        .await?;
+    if count != menu_ids.len() as u64 {
+        return Err(ApiError::Biz(String::from("菜单不存在")));
+    }
+
+    let txn = db.begin().await?;
+    SysRoleMenu::delete_many()
+        .filter(sys_role_menu::Column::RoleId.eq(&id))
+        .exec(&txn)
+        .await?;
+    if !menu_ids.is_empty() {
+        SysRoleMenu::insert_many(menu_ids.into_iter().map(|menu_id| {
+            sys_role_menu::ActiveModel {
+                role_id: ActiveValue::Set(id.clone()),
+                menu_id: ActiveValue::Set(menu_id),
+            }
+        }))
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    Ok(ApiResponse::ok("ok", None))
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct DataScopeParams {
+    pub data_scope: DataScope,
+    // 自定义数据权限时可以访问的部门
+    #[serde(default)]
+    pub dept_ids: Vec<String>,
+}
+
+#[debug_handler]
+async fn role_data_scope(
+    State(AppState { db }): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<DataScopeParams>> {
+    let role = SysRole::find_by_id(&id).one(&db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("角色不存在")))?;
+    let dept_ids = SysRoleDept::find()
+        .filter(sys_role_dept::Column::RoleId.eq(&id))
+        .all(&db)
+        .await?
+        .into_iter()
+        .map(|role_dept| role_dept.dept_id)
+        .collect();
+
+    Ok(ApiResponse::ok("ok", Some(DataScopeParams { data_scope: role.data_scope, dept_ids })))
+}
+
+// 设置角色的数据权限, 非自定义范围时清空部门
+#[debug_handler]
+async fn assign_data_scope(
+    State(AppState { db }): State<AppState>,
+    principal: Principal,
+    authorities: Authorities,
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+    ValidJson(DataScopeParams { data_scope, mut dept_ids }): ValidJson<DataScopeParams>,
+) -> ApiResult<ApiResponse<()>> {
+    let role = SysRole::find_by_id(&id).one(&db).await?
+        .ok_or_else(|| ApiError::Biz(String::from("角色不存在")))?;
+    ensure_role_editable(&db, &principal, &authorities, &role).await?;
+    if data_scope == DataScope::Custom {
+        dept_ids.sort();
+        dept_ids.dedup();
+        let count = SysDept::find()
+            .filter(sys_dept::Column::Id.is_in(&dept_ids))
+            .count(&db)
+            .await?;
+        if count != dept_ids.len() as u64 {
+            return Err(ApiError::Biz(String::from("部门不存在")));
+        }
+    } else {
+        dept_ids.clear();
+    }
+    if !data_permission.covers(data_scope, &dept_ids) {
+        return Err(ApiError::Forbidden(String::from("不能设置超出自身数据权限的范围")));
+    }
+
+    let txn = db.begin().await?;
+    let mut active_model = role.into_active_model();
+    active_model.data_scope = ActiveValue::Set(data_scope);
+    active_model.updated_at = ActiveValue::Set(Local::now().naive_local());
+    active_model.update(&txn).await?;
+    SysRoleDept::delete_many()
+        .filter(sys_role_dept::Column::RoleId.eq(&id))
+        .exec(&txn)
+        .await?;
+    if !dept_ids.is_empty() {
+        SysRoleDept::insert_many(dept_ids.into_iter().map(|dept_id| {
+            sys_role_dept::ActiveModel {
+                role_id: ActiveValue::Set(id.clone()),
+                dept_id: ActiveValue::Set(dept_id),
+            }
+        }))
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    Ok(ApiResponse::ok("ok", None))
+}
+
+/*
+* 防止通过修改角色提升权限
+*
+* 超级管理员以外的用户不能修改超级管理员角色, 也不能修改自己拥有的角色(包括已禁用的)
+*/
+async fn ensure_role_editable(
+    db: &DatabaseConnection,
+    principal: &Principal,
+    authorities: &Authorities,
+    role: &sys_role::Model,
+) -> ApiResult<()> {
+    if authorities.is_super_admin() {
+        return Ok(());
+    }
+    if role.code == SUPER_ADMIN {
+        return Err(ApiError::Forbidden(String::from("只有超级管理员可以修改超级管理员角色")));
+    }
+    let owned = SysUserRole::find()
+        .filter(sys_user_role::Column::UserId.eq(&principal.id))
+        .filter(sys_user_role::Column::RoleId.eq(&role.id))
+        .count(db)
+        .await?;
+    if owned > 0 {
+        return Err(ApiError::Forbidden(String::from("不能修改自己拥有的角色")));
+    }
+    Ok(())
+}
+
+async fn ensure_code_unique(db: &DatabaseConnection, code: &str, exclude_id: Option<&str>) -> ApiResult<()> {
+    let count = SysRole::find()
+        .filter(sys_role::Column::Code.eq(code))
+        .apply_if(exclude_id, |query, id| query.filter(sys_role::Column::Id.ne(id)))
+        .count(db)
+        .await?;
+    if count > 0 {
+        return Err(ApiError::Biz(String::from("角色编码已存在")));
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use axum::{Router, debug_handler, routing};
+use axum::extract::State;
+use sea_orm::{prelude::*, Condition, PaginatorTrait, QueryTrait};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::entity::prelude::{SysUser, SysUserSession};
+use crate::entity::{sys_user, sys_user_session};
+use crate::framework::AppState;
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::auth::{data_scope::DataPermission, permission::require_permission, session};
+use crate::framework::common::{Page, PaginationParams, SortFields};
+use crate::framework::error::{ApiError, ApiResult};
+use crate::framework::request::param_valid::Path;
+use crate::framework::request::valid::ValidQuery;
+use crate::framework::response::ApiResponse;
+
+// 在线用户
+pub fn create_router() -> Router<AppState> {
+    Router::new()
+        .route("/page", routing::get(page_session).route_layer(require_permission("system:session:list")))
+        .route("/kick/{id}", routing::post(kick_session).route_layer(require_permission("system:session:kick")).route_layer(oper_log("在线用户", "强制下线")))
+        .route("/kick/user/{id}", routing::post(kick_user_sessions).route_layer(require_permission("system:session:kick")).route_layer(oper_log("在线用户", "用户全部下线")))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionQueryParams {
+    // 账号或名称
+    keyword: Option<String>,
+    user_id: Option<String>,
+
+    #[validate(nested)]
+    #[serde(flatten)]
+    pagination: PaginationParams,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnlineSession {
+    #[serde(flatten)]
+    pub session: sys_user_session::Model,
+    pub account: String,
+    pub name: String,
+}
+
+const SESSION_SORT_FIELDS: SortFields<sys_user_session::Column> = &[
+    ("loginAt", sys_user_session::Column::LoginAt),
+    ("lastSeenAt", sys_user_session::Column::LastSeenAt),
+    ("expiresAt", sys_user_session::Column::ExpiresAt),
+];
+
+// 只返回数据权限范围内用户的会话
+#[debug_handler]
+async fn page_session(
+    State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
+    ValidQuery(SessionQueryParams {
+        keyword,
+        user_id,
+        mut pagination,
+    }): ValidQuery<SessionQueryParams>,
+) -> ApiResult<ApiResponse<Page<OnlineSession>>> {
+    let query = SysUserSession::find()
+        .find_also_related(SysUser)
+        .filter(session::active())
+        .filter(data_permission.condition(sys_user::Column::DeptId, sys_user::Column::Id))
+        .apply_if(user_id.as_ref(), |query, user_id| {
+            query.filter(sys_user_session::Column::UserId.eq(user_id))
+        })
+        .apply_if(keyword.as_ref(), |query, keyword| {
+            query.filter(
+                Condition::any()
+                    .add(sys_user::Column::Account.contains(keyword))
+                    .add(sys_user::Column::Name.contains(keyword)),
+            )
+        });
+    let paginator = pagination
+        .apply_sort(query, SESSION_SORT_FIELDS, "lastSeenAt,desc")?
+        .paginate(&db, pagination.size);
+
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(pagination.page - 1)
+        .await?
+        .into_iter()
+        .filter_map(|(session, user)| {
+            user.map(|user| OnlineSession { session, account: user.account, name: user.name })
+        })
+        .collect();
+
+    Ok(ApiResponse::ok("ok", Some(Page::from_pagination(pagination, total, items))))
+}
+
+// 踢掉单个会话, 该会话的 token 立即失效
+#[debug_handler]
+async fn kick_session(
+    State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<()>> {
+    SysUserSession::find_by_id(&id)
+        .inner_join(SysUser)
+        .filter(data_permission.condition(sys_user::Column::DeptId, sys_user::Column::Id))
+        .one(&db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("会话不存在")))?;
+
+    if !session::revoke(&db, &id).await? {
+        return Err(ApiError::Biz(String::from("会话已结束")));
+    }
+    tracing::info!("kick session: {}", id);
+    Ok(ApiResponse::ok("ok", None))
+}
+
+// 踢掉用户的所有会话
+#[debug_handler]
+async fn kick_user_sessions(
+    State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<u64>> {
+    SysUser::find_by_id(&id)
+        .filter(data_permission.condition(sys_user::Column::DeptId, sys_user::Column::Id))
+        .one(&db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(String::from("用户不存在")))?;
+
+    let kicked = session::revoke_user(&db, &id).await?;
+    tracing::info!("kick user sessions: {}, {} sessions", id, kicked);
+    Ok(ApiResponse::ok("ok", Some(kicked)))
+}
@@ -1,59 +1,166 @@
 use anyhow::Context;
 use axum::{Router, debug_handler, routing};
 use axum::extract::State;
-use sea_orm::{prelude::*, ActiveValue, IntoActiveModel};
+use chrono::Local;
+use sea_orm::{prelude::*, ActiveValue, IntoActiveModel, JoinType, QuerySelect, TransactionTrait};
 use sea_orm::{
-    ColumnTrait, Condition, DeriveIntoActiveModel, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QueryTrait
+    ColumnTrait, Condition, DeriveIntoActiveModel, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder
 };
 use serde::Deserialize;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 use crate::entity::sys_user::ActiveModel;
-use crate::entity::{prelude::SysUser, sys_user};
+use crate::entity::prelude::{SysDept, SysPermission, SysRole, SysRoleDept, SysUser, SysUserRole};
+use crate::entity::{sys_permission, sys_role, sys_role_dept, sys_role_permission, sys_user, sys_user_role};
 use crate::enums::Gender;
 use crate::framework::request::param_valid::Path;
 use crate::framework::AppState;
-use crate::framework::common::{Page, PaginationParams};
+use crate::framework::middleware::oper_log::oper_log;
+use crate::framework::auth::{data_scope::DataPermission, lockout, mfa, password, provider, session};
+use crate::framework::auth::permission::{require_permission, Authorities, SUPER_ADMIN};
+use crate::framework::common::{Page, PaginationParams, SortFields};
 use crate::framework::error::{ApiError, ApiResult};
 use crate::framework::response::ApiResponse;
+use crate::framework::serde::{deserialize_nullable, deserialize_optional};
+use crate::framework::request::filter::{self, Filter, FilterField, FilterFields, Operator};
 use crate::framework::request::valid::{ValidJson, ValidQuery};
+use crate::framework::utils::validation;
 
 pub fn create_router() -> Router<AppState> {
     Router::new()
-        .route("/", routing::get(query_users))
-        .route("/page", routing::get(page_user))
-        .route("/create", routing::post(create_user))
-        .route("/update/{id}", routing::put(update_user))
-        .route("/delete/{id}", routing::delete(delete_user))
+        .route("/", routing::get(query_users).route_layer(require_permission("system:user:list")))
+        .route("/page", routing::get(page_user).route_layer(require_permission("system:user:list")))
+        .route("/{id}", routing::get(get_user).route_layer(require_permission("system:user:list")))
+        .route("/create", routing::post(create_user).route_layer(require_permission("system:user:create")).route_layer(oper_log("用户管理", "新增用户")))
+        .route("/update/{id}", routing::patch(update_user).put(update_user).route_layer(require_permission("system:user:update")).route_layer(oper_log("用户管理", "修改用户")))
+        .route("/delete/{id}", routing::delete(delete_user).route_layer(require_permission("system:user:delete")).route_layer(oper_log("用户管理", "删除用户")))
+        .route("/revoke/{id}", routing::post(revoke_user_sessions).route_layer(require_permission("system:user:revoke")).route_layer(oper_log("用户管理", "强制下线")))
+        .route("/unlock/{id}", routing::post(unlock_user).route_layer(require_permission("system:user:unlock")).route_layer(oper_log("用户管理", "解除锁定")))
+        .route("/mfa/reset/{id}", routing::post(reset_user_mfa).route_layer(require_permission("system:user:mfa")).route_layer(oper_log("用户管理", "重置两步验证")))
+        .route("/roles/{id}", routing::get(user_roles).route_layer(require_permission("system:user:list")))
+        .route("/roles/{id}", routing::put(assign_roles).route_layer(require_permission("system:user:role")).route_layer(oper_log("用户管理", "分配角色")))
 }
 
-#[derive(Debug, Deserialize, Validate)]
+// 用户列表和分页共用的查询条件
+#[derive(Debug, Default, Deserialize, Validate)]
 #[serde(rename_all = "camelCase")]
-pub struct UserQueryParams {
+#[validate(schema(function = "validate_user_filter"))]
+pub struct UserFilter {
+    // 姓名或账号
     keyword: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    gender: Option<Gender>,
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    enabled: Option<bool>,
+    #[validate(length(max = 20, message = "账号长度不能超过20"))]
+    account: Option<String>,
+    #[validate(length(max = 11, message = "手机号长度不能超过11"))]
+    mobile_phone: Option<String>,
+    // 生日范围, 格式 2000-01-01
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    birthday_begin: Option<Date>,
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    birthday_end: Option<Date>,
+    // 创建时间范围, 格式 2025-01-01T00:00:00
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    begin_time: Option<DateTime>,
+    #[serde(default, deserialize_with = "deserialize_optional")]
+    end_time: Option<DateTime>,
+}
+
+fn validate_user_filter(filter: &UserFilter) -> Result<(), ValidationError> {
+    validation::check_range(filter.birthday_begin.as_ref(), filter.birthday_end.as_ref(), "生日开始日期不能晚于结束日期")?;
+    validation::check_range(filter.begin_time.as_ref(), filter.end_time.as_ref(), "开始时间不能晚于结束时间")
+}
+
+impl UserFilter {
+    pub fn condition(&self) -> Condition {
+        Condition::all()
+            .add_option(self.keyword.as_ref().map(|keyword| {
+                Condition::any()
+                    .add(filter::contains(sys_user::Column::Name, keyword))
+                    .add(filter::contains(sys_user::Column::Account, keyword))
+            }))
+            .add_option(self.gender.map(|gender| sys_user::Column::Gender.eq(gender)))
+            .add_option(self.enabled.map(|enabled| sys_user::Column::Enabled.eq(enabled)))
+            .add_option(self.account.as_ref().map(|account| filter::contains(sys_user::Column::Account, account)))
+            .add_option(self.mobile_phone.as_ref().map(|mobile_phone| filter::contains(sys_user::Column::MobilePhone, mobile_phone)))
+            .add_option(self.birthday_begin.map(|birthday| sys_user::Column::Birthday.gte(birthday)))
+            .add_option(self.birthday_end.map(|birthday| sys_user::Column::Birthday.lte(birthday)))
+            .add_option(self.begin_time.map(|time| sys_user::Column::CreatedAt.gte(time)))
+            .add_option(self.end_time.map(|time| sys_user::Column::CreatedAt.lte(time)))
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserQueryParams {
+    #[validate(nested)]
+    #[serde(flatten)]
+    filter: UserFilter,
 
     // 嵌套校验内层结构
     #[validate(nested)]
     #[serde(flatten)]
     pagination: PaginationParams,
+
+    // 其余参数按 name[like]=ab&createdAt[gte]=2025-01-01&deptId[in]=1,2 解析
+    #[serde(flatten)]
+    filters: Filter<UserFilterFields>,
 }
 
+// 用户列表的查询参数, 与分页使用同样的过滤条件, 未知参数直接报错
+#[derive(Debug, Deserialize, Validate)]
+pub struct UserListParams {
+    #[validate(nested)]
+    #[serde(flatten)]
+    filter: UserFilter,
+
+    #[serde(flatten)]
+    filters: Filter<UserFilterFields>,
+}
+
+#[derive(Debug)]
+pub struct UserFilterFields;
+
+// account、mobilePhone、gender、enabled 已经由 UserFilter 处理, 不再重复提供, 同一个参数只有一种含义
+impl FilterFields for UserFilterFields {
+    type Column = sys_user::Column;
+    const FIELDS: &'static [FilterField<Self::Column>] = &[
+        FilterField::new("name", sys_user::Column::Name, filter::TEXT),
+        FilterField::new("email", sys_user::Column::Email, &[Operator::Eq, Operator::Like, Operator::Null]),
+        FilterField::new("authSource", sys_user::Column::AuthSource, filter::ENUM),
+        FilterField::new("deptId", sys_user::Column::DeptId, &[Operator::Eq, Operator::In, Operator::Null]),
+        FilterField::new("birthday", sys_user::Column::Birthday, filter::RANGE),
+        FilterField::new("createdAt", sys_user::Column::CreatedAt, filter::RANGE),
+    ];
+}
+
+// 允许排序的字段
+const USER_SORT_FIELDS: SortFields<sys_user::Column> = &[
+    ("name", sys_user::Column::Name),
+    ("account", sys_user::Column::Account),
+    ("gender", sys_user::Column::Gender),
+    ("birthday", sys_user::Column::Birthday),
+    ("enabled", sys_user::Column::Enabled),
+    ("createdAt", sys_user::Column::CreatedAt),
+    ("updatedAt", sys_user::Column::UpdatedAt),
+];
+
 // 原始的错误信息并不明确，需要结束这个宏来debug
 // 帮助打印发生异常时候的错误信息，方便分析问题
 // 这个在打发行包的时候不会编译，不会带来生产环境开销
 #[debug_handler]
 async fn query_users(
     State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
+    ValidQuery(UserListParams { filter, filters }): ValidQuery<UserListParams>,
 ) -> ApiResult<ApiResponse<Vec<sys_user::Model>>> {
     let users = SysUser::find()
-        // filter only one condition
-        // sea_orm::condition compose multiple conditions
-        .filter(
-            Condition::all()
-                .add(sys_user::Column::Gender.eq("female"))
-                .add(sys_user::Column::Name.starts_with("A"))
-                .add(Condition::any().add(sys_user::Column::Enabled.eq(false))),
-        )
+        // 只返回数据权限范围内的用户
+        .filter(data_permission.condition(sys_user::Column::DeptId, sys_user::Column::Id))
+        .filter(filter.condition())
+        .filter(filters.condition())
+        .order_by_desc(sys_user::Column::CreatedAt)
         .all(&db)
         .await
         .context("query users error")?;
@@ -64,22 +171,21 @@ async fn query_users(
 #[debug_handler]
 async fn page_user(
     State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
     // Query 抽取器取出参数
     // Valid 将抽取出来的结果进行校验
     ValidQuery(UserQueryParams {
-        keyword,
-        pagination,
+        filter,
+        mut pagination,
+        filters,
     }): ValidQuery<UserQueryParams>
 ) -> ApiResult<ApiResponse<Page<sys_user::Model>>> {
-    let paginator = SysUser::find()
-        .apply_if(keyword.as_ref(), |query, keyword| {
-            query.filter(
-                Condition::any()
-                    .add(sys_user::Column::Name.contains(keyword))
-                    .add(sys_user::Column::Account.contains(keyword)),
-            )
-        })
-        .order_by_desc(sys_user::Column::CreatedAt)
+    let query = SysUser::find()
+        .filter(data_permission.condition(sys_user::Column::DeptId, sys_user::Column::Id))
+        .filter(filter.condition())
+        .filter(filters.condition());
+    let paginator = pagination
+        .apply_sort(query, USER_SORT_FIELDS, "createdAt,desc")?
         .paginate(&db, pagination.size);
 
     let total = paginator.num_items().await?;
@@ -89,7 +195,20 @@ async fn page_user(
     Ok(ApiResponse::ok("ok", Some(page)))
 }
 
+#[debug_handler]
+async fn get_user(
+    State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<sys_user::Model>> {
+    let user = find_user(&db, &data_permission, &id, "用户不存在").await?;
+
+    Ok(ApiResponse::ok("ok", Some(user)))
+}
+
 #[derive(Debug, Deserialize, Validate, DeriveIntoActiveModel)]
+// 结构体级别的校验器, 可以同时访问多个字段
+#[validate(schema(function = "validate_user_password"))]
 pub struct UserParams {
 
     #[validate(length(min = 2, max = 20, message = "姓名长度1-20"))]
@@ -98,73 +217,363 @@ pub struct UserParams {
     #[validate(length(min = 1, max = 20, message = "账号长度1-20"))]
     pub account: String,
 
-    // 常见的简单校验器已经内置
-    #[validate(length(min = 6, max = 20, message = "密码长度6-20"))]
+    // 按配置的密码策略校验, 且不能与账号、手机号相同
     pub password: String,
 
     // 自定义的校验器，校验手机号, 指定方法，方法中返回的错误将作为校验失败的错误
     #[validate(custom(function = "crate::framework::utils::validation::is_mobile_phone"))]
     pub mobile_phone: String,
 
+    #[validate(email(message = "邮箱格式不正确"))]
+    pub email: Option<String>,
+
     pub birthday: Date,
     #[serde(default)]
     pub enabled: bool,
+    pub dept_id: Option<String>,
+}
+
+fn validate_user_password(params: &UserParams) -> Result<(), ValidationError> {
+    validation::check_password(&params.password, &[&params.account, &params.mobile_phone])
 }
 
 #[debug_handler]
 pub async fn create_user(
     State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
     ValidJson(user_params): ValidJson<UserParams>
 ) -> ApiResult<ApiResponse<sys_user::Model>> {
+    ensure_dept_allowed(&db, &data_permission, user_params.dept_id.as_deref()).await?;
+    let duplicated = SysUser::find().filter(sys_user::Column::Account.eq(&user_params.account)).one(&db).await?;
+    if duplicated.is_some() {
+        return Err(ApiError::Biz(String::from("账号已存在")));
+    }
+    let duplicated = SysUser::find().filter(sys_user::Column::Name.eq(&user_params.name)).one(&db).await?;
+    if duplicated.is_some() {
+        return Err(ApiError::Biz(String::from("姓名已被使用")));
+    }
     let mut user_model  = user_params.into_active_model();
-    user_model.password = ActiveValue::Set(
-        bcrypt::hash(
-            &user_model.password.take().unwrap(),
-            bcrypt::DEFAULT_COST
-        )?
-    );
-
-    let result = user_model.insert(&db).await?;
+    let hashed = password::hash(&user_model.password.take().unwrap())?;
+    user_model.password = ActiveValue::Set(hashed.clone());
+
+    let txn = db.begin().await?;
+    let result = user_model.insert(&txn).await?;
+    password::record(&txn, &result.id, &hashed).await?;
+    txn.commit().await?;
     Ok(ApiResponse::ok("ok", Some(result)))
 }
 
+// 修改用户时所有字段都是可选的, 只更新传入的字段; 字段名与新增时一致, 不认识的字段直接报错
+#[derive(Debug, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct UserUpdateParams {
+    #[validate(length(min = 2, max = 20, message = "姓名长度2-20"))]
+    pub name: Option<String>,
+    pub gender: Option<Gender>,
+    #[validate(length(min = 1, max = 20, message = "账号长度1-20"))]
+    pub account: Option<String>,
+    // 不传时保持不变, 传入时按密码策略校验
+    #[validate(custom(function = "crate::framework::utils::validation::is_strong_password"))]
+    pub password: Option<String>,
+    #[validate(custom(function = "crate::framework::utils::validation::is_mobile_phone"))]
+    pub mobile_phone: Option<String>,
+    // 传 null 时清空
+    #[validate(email(message = "邮箱格式不正确"))]
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub email: Option<Option<String>>,
+    pub birthday: Option<Date>,
+    pub enabled: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub dept_id: Option<Option<String>>,
+}
+
 #[debug_handler]
 pub async fn update_user(
     State(AppState { db }): State<AppState>,
-    Path(id):Path<String>,
-    ValidJson(user_params): ValidJson<UserParams>
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+    ValidJson(params): ValidJson<UserUpdateParams>
 ) -> ApiResult<ApiResponse<sys_user::Model>> {
-    let existed_user = SysUser::find_by_id(id).one(&db).await?
-        .ok_or_else(|| ApiError::Biz(String::from("待修改用户不存在")))?;
-    let pwd = user_params.password.clone();
-    let mut active_model = user_params.into_active_model();
-    if pwd.is_empty() {
-        active_model.password = ActiveValue::unchanged(existed_user.password);
-    }else {
-        active_model.password = ActiveValue::Set(bcrypt::hash(
-            &active_model.password.take().unwrap(),
-            bcrypt::DEFAULT_COST
-        )?);
-    }
-    let result =active_model.update(&db).await?;
+    let existed_user = find_user(&db, &data_permission, &id, "待修改用户不存在").await?;
+    // 不能把用户移到数据权限范围外的部门
+    if let Some(dept_id) = &params.dept_id {
+        ensure_dept_allowed(&db, &data_permission, dept_id.as_deref()).await?;
+    }
+    if let Some(name) = params.name.as_ref().filter(|name| **name != existed_user.name) {
+        let duplicated = SysUser::find().filter(sys_user::Column::Name.eq(name)).one(&db).await?;
+        if duplicated.is_some() {
+            return Err(ApiError::Biz(String::from("姓名已被使用")));
+        }
+    }
+    if let Some(account) = params.account.as_ref().filter(|account| **account != existed_user.account) {
+        let duplicated = SysUser::find().filter(sys_user::Column::Account.eq(account)).one(&db).await?;
+        if duplicated.is_some() {
+            return Err(ApiError::Biz(String::from("账号已存在")));
+        }
+    }
+
+    let mut new_password = None;
+    if let Some(pwd) = &params.password {
+        if !provider::is_local(&existed_user) {
+            return Err(ApiError::Biz(String::from("外部认证的账号不能设置密码")));
+        }
+        // 按修改后的账号和手机号校验
+        let updated = sys_user::Model {
+            account: params.account.clone().unwrap_or_else(|| existed_user.account.clone()),
+            mobile_phone: params.mobile_phone.clone().unwrap_or_else(|| existed_user.mobile_phone.clone()),
+            ..existed_user.clone()
+        };
+        password::check_policy(pwd, &updated)?;
+        password::ensure_not_reused(&db, &existed_user, pwd).await?;
+        new_password = Some(password::hash(pwd)?);
+    }
+
+    let mut active_model = existed_user.clone().into_active_model();
+    if let Some(name) = params.name {
+        active_model.name = ActiveValue::Set(name);
+    }
+    if let Some(gender) = params.gender {
+        active_model.gender = ActiveValue::Set(gender);
+    }
+    if let Some(account) = params.account {
+        active_model.account = ActiveValue::Set(account);
+    }
+    if let Some(hashed) = &new_password {
+        active_model.password = ActiveValue::Set(hashed.clone());
+        active_model.password_updated_at = ActiveValue::Set(Local::now().naive_local());
+    }
+    if let Some(mobile_phone) = params.mobile_phone {
+        active_model.mobile_phone = ActiveValue::Set(mobile_phone);
+    }
+    if let Some(email) = params.email {
+        active_model.email = ActiveValue::Set(email);
+    }
+    if let Some(birthday) = params.birthday {
+        active_model.birthday = ActiveValue::Set(birthday);
+    }
+    if let Some(enabled) = params.enabled {
+        active_model.enabled = ActiveValue::Set(enabled);
+    }
+    if let Some(dept_id) = params.dept_id {
+        active_model.dept_id = ActiveValue::Set(dept_id);
+    }
+    if !active_model.is_changed() {
+        return Ok(ApiResponse::ok("ok", Some(existed_user)));
+    }
+    active_model.updated_at = ActiveValue::Set(Local::now().naive_local());
+
+    let txn = db.begin().await?;
+    let result = active_model.update(&txn).await?;
+    if let Some(hashed) = &new_password {
+        password::record(&txn, &result.id, hashed).await?;
+    }
+    txn.commit().await?;
+    // 禁用用户或重置密码时结束其所有登录
+    if (existed_user.enabled && !result.enabled) || new_password.is_some() {
+        session::revoke_user(&db, &result.id).await?;
+    }
 
     Ok(ApiResponse::ok("ok", Some(result)))
-}   
+}
 
 #[debug_handler]
 pub async fn delete_user(
     State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
     Path(id): Path<String>,
 ) -> ApiResult<ApiResponse<()>> { 
 
-    let exists_user = SysUser::find_by_id(&id)
-        .one(&db)
-        .await?
-        .ok_or_else(|| ApiError::Biz(String::from("待删除用户不存在")))?;
+    let exists_user = find_user(&db, &data_permission, &id, "待删除用户不存在").await?;
 
     // effect rows
     let result = exists_user.delete(&db).await?;
+    session::revoke_user(&db, &id).await?;
     tracing::info!("delete user: {}, rows: {}", id, result.rows_affected);
     Ok(ApiResponse::ok("ok", None))
 }
 
+// 强制用户下线: 已签发的 access token 与 refresh token 全部失效
+#[debug_handler]
+pub async fn revoke_user_sessions(
+    State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<()>> {
+    find_user(&db, &data_permission, &id, "用户不存在").await?;
+
+    session::revoke_user(&db, &id).await?;
+    tracing::info!("revoke user sessions: {}", id);
+    Ok(ApiResponse::ok("ok", None))
+}
+
+// 解除登录失败导致的账号锁定
+#[debug_handler]
+pub async fn unlock_user(
+    State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<()>> {
+    let user = find_user(&db, &data_permission, &id, "用户不存在").await?;
+
+    lockout::unlock(&user.account);
+    tracing::info!("unlock user: {}", user.account);
+    Ok(ApiResponse::ok("ok", None))
+}
+
+// 用户丢失验证器且没有恢复码时, 由管理员关闭其两步验证
+#[debug_handler]
+pub async fn reset_user_mfa(
+    State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<()>> {
+    let user = find_user(&db, &data_permission, &id, "用户不存在").await?;
+
+    mfa::disable(&db, &user.id).await?;
+    tracing::info!("reset user mfa: {}", user.account);
+    Ok(ApiResponse::ok("ok", None))
+}
+
+#[debug_handler]
+pub async fn user_roles(
+    State(AppState { db }): State<AppState>,
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+) -> ApiResult<ApiResponse<Vec<sys_role::Model>>> {
+    find_user(&db, &data_permission, &id, "用户不存在").await?;
+    let roles = SysRole::find()
+        .join(JoinType::InnerJoin, sys_role::Relation::SysUserRole.def())
+        .filter(sys_user_role::Column::UserId.eq(id))
+        .order_by_asc(sys_role::Column::CreatedAt)
+        .all(&db)
+        .await?;
+
+    Ok(ApiResponse::ok("ok", Some(roles)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignRoleParams {
+    pub role_ids: Vec<String>,
+}
+
+// 覆盖用户的角色
+#[debug_handler]
+pub async fn assign_roles(
+    State(AppState { db }): State<AppState>,
+    authorities: Authorities,
+    data_permission: DataPermission,
+    Path(id): Path<String>,
+    ValidJson(AssignRoleParams { mut role_ids }): ValidJson<AssignRoleParams>,
+) -> ApiResult<ApiResponse<()>> {
+    find_user(&db, &data_permission, &id, "用户不存在").await?;
+    role_ids.sort();
+    role_ids.dedup();
+    let roles = SysRole::find()
+        .filter(sys_role::Column::Id.is_in(&role_ids))
+        .all(&db)
+        .await?;
+    if roles.len() != role_ids.len() {
+        return Err(ApiError::Biz(String::from("角色不存在")));
+    }
+    let current = SysRole::find()
+        .join(JoinType::InnerJoin, sys_role::Relation::SysUserRole.def())
+        .filter(sys_user_role::Column::UserId.eq(&id))
+        .all(&db)
+        .await?;
+    ensure_grantable(&db, &authorities, &data_permission, &current, &roles).await?;
+
+    let txn = db.begin().await?;
+    SysUserRole::delete_many()
+        .filter(sys_user_role::Column::UserId.eq(&id))
+        .exec(&txn)
+        .await?;
+    if !role_ids.is_empty() {
+        SysUserRole::insert_many(role_ids.into_iter().map(|role_id| {
+            sys_user_role::ActiveModel {
+                user_id: ActiveValue::Set(id.clone()),
+                role_id: ActiveValue::Set(role_id),
+            }
+        }))
+        .exec(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    Ok(ApiResponse::ok("ok", None))
+}
+
+/*
+* 防止通过分配角色提升权限
+*
+* 只有超级管理员可以授予或收回超级管理员角色;
+* 新授予的角色, 其权限必须都在当前用户自己的权限范围内, 数据权限也不能超出当前用户的范围
+*/
+async fn ensure_grantable(
+    db: &DatabaseConnection,
+    authorities: &Authorities,
+    data_permission: &DataPermission,
+    current: &[sys_role::Model],
+    requested: &[sys_role::Model],
+) -> ApiResult<()> {
+    if authorities.is_super_admin() {
+        return Ok(());
+    }
+    let granted: Vec<&sys_role::Model> = requested
+        .iter()
+        .filter(|role| !current.iter().any(|existed| existed.id == role.id))
+        .collect();
+    let revoked = current.iter().filter(|role| !requested.iter().any(|kept| kept.id == role.id));
+    if granted.iter().copied().chain(revoked).any(|role| role.code == SUPER_ADMIN) {
+        return Err(ApiError::Forbidden(String::from("只有超级管理员可以分配超级管理员角色")));
+    }
+    if granted.is_empty() {
+        return Ok(());
+    }
+
+    let permissions = SysPermission::find()
+        .join(JoinType::InnerJoin, sys_permission::Relation::SysRolePermission.def())
+        .filter(sys_role_permission::Column::RoleId.is_in(granted.iter().map(|role| role.id.as_str())))
+        .all(db)
+        .await?;
+    if let Some(permission) = permissions.iter().find(|permission| !authorities.permissions.contains(&permission.code)) {
+        return Err(ApiError::Forbidden(format!("不能分配超出自身权限的角色: {}", permission.code)));
+    }
+
+    let role_depts = SysRoleDept::find()
+        .filter(sys_role_dept::Column::RoleId.is_in(granted.iter().map(|role| role.id.as_str())))
+        .all(db)
+        .await?;
+    for role in granted {
+        let dept_ids: Vec<String> = role_depts
+            .iter()
+            .filter(|role_dept| role_dept.role_id == role.id)
+            .map(|role_dept| role_dept.dept_id.clone())
+            .collect();
+        if !data_permission.covers(role.data_scope, &dept_ids) {
+            return Err(ApiError::Forbidden(format!("不能分配超出自身数据权限的角色: {}", role.code)));
+        }
+    }
+    Ok(())
+}
+
+// 按数据权限查找用户, 范围外的用户同样视为不存在
+async fn find_user(db: &DatabaseConnection, data_permission: &DataPermission, id: &str, message: &str) -> ApiResult<sys_user::Model> {
+    SysUser::find_by_id(id)
+        .filter(data_permission.condition(sys_user::Column::DeptId, sys_user::Column::Id))
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::Biz(message.to_string()))
+}
+
+// 部门必须存在且在数据权限范围内
+async fn ensure_dept_allowed(db: &DatabaseConnection, data_permission: &DataPermission, dept_id: Option<&str>) -> ApiResult<()> {
+    if !data_permission.contains_dept(dept_id) {
+        return Err(ApiError::Forbidden(String::from("没有该部门的数据权限")));
+    }
+    if let Some(dept_id) = dept_id {
+        SysDept::find_by_id(dept_id).one(db).await?
+            .ok_or_else(|| ApiError::Biz(String::from("部门不存在")))?;
+    }
+    Ok(())
+}